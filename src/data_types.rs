@@ -1,21 +1,84 @@
 use crate::errors::Err;
 use crate::nodes::Node;
 use crate::panic;
+use crate::scope::Scope;
 
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 
 use rayon::prelude::*;
 
+/// A function implemented in Rust, either by the standard
+/// library or by whoever is embedding the interpreter.
+pub type NativeFn = Arc<dyn Fn(Vec<Variable>, &mut Scope) -> anyhow::Result<Variable> + Send + Sync>;
+
 pub enum Variable {
     Str(String),
-    Num(f32),
+    Num(f64),
     Bool(bool),
     Array(Vec<Variable>),
+    /// String keys to values. Also how library namespaces
+    /// are stored, e.g. the `internet` in `internet.get(...)`.
+    Map(BTreeMap<String, Variable>),
     Function(Node),
-    NativeFunction(fn(Vec<Variable>, &mut HashMap<String, Variable>) -> Variable),
+    NativeFunction(NativeFn),
+    /// Something stateful owned by a library, like an open file.
+    Handle(Handle),
+    /// Markup made by the `html` library (or vouched for with
+    /// `html.raw`), which is never escaped again.
+    Html(String),
     Void,
 }
 
+/// What an iterator handle yields. Each item can fail, e.g. if
+/// reading the next line of a file goes wrong.
+pub type LazyIter = Box<dyn Iterator<Item = anyhow::Result<Variable>> + Send>;
+
+/// A shared reference to a resource held by a library (an open file,
+/// a lazy iterator...). Cloning it does not clone the resource.
+#[derive(Clone)]
+pub struct Handle {
+    /// What sort of resource this is, e.g. `"file"`.
+    pub kind: &'static str,
+    inner: Arc<Mutex<Box<dyn Any + Send>>>,
+}
+
+impl Handle {
+    pub fn new<T: Any + Send>(kind: &'static str, resource: T) -> Self {
+        Handle {
+            kind,
+            inner: Arc::new(Mutex::new(Box::new(resource))),
+        }
+    }
+
+    /// A handle that `for` can loop over without collecting
+    /// everything into an array first.
+    pub fn iterator(iter: impl Iterator<Item = anyhow::Result<Variable>> + Send + 'static) -> Self {
+        Handle::new("iterator", Box::new(iter) as LazyIter)
+    }
+
+    /// Gives `func` access to the resource, failing if this is not
+    /// a handle of the `kind` expected.
+    pub fn with<T: Any, R>(
+        &self,
+        kind: &'static str,
+        func: impl FnOnce(&mut T) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.downcast_mut::<T>() {
+            Some(resource) => func(resource),
+            None => anyhow::bail!(Err::HandleMismatch(kind, self.kind)),
+        }
+    }
+
+    /// Pulls the next item out of an iterator handle.
+    pub fn next_item(&self) -> anyhow::Result<Option<Variable>> {
+        self.with("iterator", |iter: &mut LazyIter| iter.next().transpose())
+    }
+}
+
 impl std::fmt::Display for Variable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
         use Variable::*;
@@ -41,6 +104,18 @@ impl std::fmt::Display for Variable {
                     out.push(']');
                     out
                 }
+                Map(map) => {
+                    let mut out = String::from("{");
+                    for (key, value) in map {
+                        out.push(' ');
+                        out += key;
+                        out += ": ";
+                        out += &value.to_string();
+                        out.push(' ');
+                    }
+                    out.push('}');
+                    out
+                }
                 Function(func) => match func {
                     Node::FunctionDecl { name, args, nodes } => {
                         format!("@{} [{:?}] -> {{ {:?} }}", name, args, nodes)
@@ -57,6 +132,8 @@ impl std::fmt::Display for Variable {
                 NativeFunction(_) => {
                     String::from("<[Native function representing not supported.]>")
                 }
+                Handle(handle) => format!("<{} handle>", handle.kind),
+                Html(html) => html.to_string(),
                 Void => String::from("()"),
             }
         )
@@ -71,9 +148,12 @@ impl Clone for Variable {
             Variable::Num(num) => Variable::Num(*num),
             Variable::Bool(boolean) => Variable::Bool(*boolean),
             Variable::Array(array) => Variable::Array(array.clone()),
+            Variable::Map(map) => Variable::Map(map.clone()),
             // todo: impl copy for Nodes
             Variable::Function(func) => Variable::Function(func.clone()),
             Variable::NativeFunction(func) => Variable::NativeFunction(func.clone()),
+            Variable::Handle(handle) => Variable::Handle(handle.clone()),
+            Variable::Html(html) => Variable::Html(html.clone()),
             Variable::Void => Variable::Void,
         }
     }
@@ -81,13 +161,19 @@ impl Clone for Variable {
 
 impl std::cmp::PartialEq for Variable {
     fn eq(&self, rhs: &Variable) -> bool {
-        return match self {
+        match self {
             Variable::Array(arr) => {
                 arr == match rhs {
                     Variable::Array(arr) => arr,
                     _ => return false,
                 }
             }
+            Variable::Map(map) => {
+                map == match rhs {
+                    Variable::Map(map) => map,
+                    _ => return false,
+                }
+            }
             Variable::Bool(b) => {
                 b == match rhs {
                     Variable::Bool(b) => b,
@@ -103,17 +189,25 @@ impl std::cmp::PartialEq for Variable {
             }
             Variable::Function(_) => panic!("Cannot compare functions"),
             Variable::NativeFunction(_) => panic!("Cannot compare functions"),
+            // Handles are only equal to themselves.
+            Variable::Handle(handle) => match rhs {
+                Variable::Handle(rhs) => Arc::ptr_eq(&handle.inner, &rhs.inner),
+                _ => false,
+            },
             Variable::Num(num) => {
                 num == match rhs {
                     Variable::Num(num) => num,
                     _ => return false,
                 }
             }
-            Variable::Void => match rhs {
-                Variable::Void => true,
-                _ => false,
-            },
-        };
+            Variable::Html(html) => {
+                html == match rhs {
+                    Variable::Html(html) => html,
+                    _ => return false,
+                }
+            }
+            Variable::Void => matches!(rhs, Variable::Void),
+        }
     }
 }
 
@@ -144,14 +238,24 @@ impl std::fmt::Debug for Variable {
 }
 
 impl Variable {
+    /// Wraps a Rust closure so that Shrimp code can call it.
+    pub fn native<F>(func: F) -> Variable
+    where
+        F: Fn(Vec<Variable>, &mut Scope) -> anyhow::Result<Variable> + Send + Sync + 'static,
+    {
+        Variable::NativeFunction(Arc::new(func))
+    }
     pub fn as_words(&self) -> String {
         match self {
             Variable::Num(_) => "a number",
             Variable::Str(_) => "a string",
             Variable::Array(_) => "an array",
+            Variable::Map(_) => "a map",
             Variable::Bool(_) => "a boolean",
             Variable::Function(_) => "a function",
             Variable::NativeFunction(_) => "a function",
+            Variable::Handle(_) => "a handle",
+            Variable::Html(_) => "HTML",
             Variable::Void => "nothing",
         }
         .to_string()
@@ -34,6 +34,12 @@ pub enum Err {
     MissingArgs(String),
     #[error("Unknown library specified `{0:?}`")]
     UnknownLib(Node),
+    #[error("Could not find a module at `{0}` (searched next to the importing file and in SHRIMP_PATH)")]
+    ModuleNotFound(String),
+    #[error("Unsupported import: {0}")]
+    UnsupportedImport(String),
+    #[error("Import cycle detected: {0}")]
+    ImportCycle(String),
     #[error("Nonexistent variable called `{0}`")]
     NonexistentVar(String),
     #[error("Unknown keyword `{0}`")]
@@ -48,18 +54,98 @@ pub enum Err {
     NoMain,
     #[error("There was in error in converting {} into a number (num function called)", .0.as_words())]
     NumParserError(Variable),
+    #[error("Unknown write mode `{0}` (expected \"truncate\", \"append\", \"create_new\" or \"overwrite\")")]
+    UnknownWriteMode(String),
+    #[error("Unknown open mode `{0}` (expected \"read\", \"write\", \"append\", \"create_new\" or \"read_write\")")]
+    UnknownOpenMode(String),
+    #[error("Expected a byte (a whole number from 0 to 255), found {0}")]
+    NotAByte(Variable),
+    #[error("Expected a {0} handle, found a {1} handle")]
+    HandleMismatch(&'static str, &'static str),
+    #[error("This file was not opened for {0}")]
+    WrongFileMode(&'static str),
+    #[error("A write mode can't be given for an open file, it was chosen by `io.open`")]
+    ModeOnOpenFile,
+    #[error("Tried to use a {0} handle after it was closed")]
+    ClosedHandle(&'static str),
+    #[error("`{0}` failed ({1}){}", if .2.is_empty() { String::new() } else { format!(": {}", .2) })]
+    CommandFailed(String, std::process::ExitStatus, String),
+    #[error("Invalid time format `{0}`")]
+    InvalidTimeFormat(String),
+    #[error("{0} milliseconds is not a valid duration")]
+    InvalidDuration(f64),
+    #[error("`{0}` is not a valid time (is it skipped by a daylight saving change?)")]
+    InvalidTime(String),
+    #[error("Timestamp {0} is out of range")]
+    TimestampOutOfRange(f64),
+    #[error("Expected a whole number, found {0}")]
+    NotAnInteger(Variable),
+    #[error("Can't shift by {0} bits (expected 0 to 63)")]
+    ShiftOutOfRange(i64),
+    #[error("Can't show {0} decimal places (expected 0 to 100)")]
+    DigitsOutOfRange(i64),
+    #[error("The {0} of {1} and {2} is too big for a whole number")]
+    IntegerOverflow(&'static str, i64, i64),
+    #[error("Can't pick a number between {0} and {1}, the minimum is larger than the maximum")]
+    EmptyRange(i64, i64),
+    #[error("Expected a single character, found `{0}`")]
+    NotAChar(String),
+    #[error("Not enough memory to repeat {0} bytes {1} times")]
+    RepeatTooLong(usize, usize),
+    #[error("Invalid format string: {0}")]
+    BadFormat(String),
+    #[error("Invalid route pattern `{0}` (`*` can only be the last segment)")]
+    BadRoutePattern(String),
+    #[error("Invalid response header {0:?} (names and values can't contain line breaks)")]
+    BadHeader(String),
+    #[error("Invalid HTML: {0}")]
+    BadHtml(String),
+    #[error("Template error in {0}: {1}")]
+    BadTemplate(String, String),
+    #[error("Invalid JSON at line {0}, column {1}: {2}")]
+    BadJson(usize, usize, String),
+    #[error("Invalid CSV: {0}")]
+    BadCsv(String),
+    #[error("Invalid {0} ({1}): {2}")]
+    BadConfig(&'static str, String, String),
+    #[error("Unknown config format `{0}` (expected `toml` or `ini`)")]
+    UnknownConfigFormat(String),
+    #[error("Invalid {0}: {1}")]
+    BadEncoding(&'static str, String),
+    #[error("Invalid UTF-8 (after {0} valid bytes)")]
+    InvalidUtf8(usize),
+    #[error("{0} can't be written as {1}")]
+    Unserializable(String, &'static str),
     #[error("Index out of bounds (len is {0} but index is {1})!")]
     OutOfBoundsIndex(usize, usize),
+    #[error("Assertion failed: {0}")]
+    AssertionFailed(String),
     #[error("The Shrimp Parser failed with the following out (NOT USER ERROR) {0}")]
-    SPEGeneric(String)
+    SPEGeneric(String),
+    /// Not really an error: the program called `exit`. It makes its way
+    /// out like one, so that the host decides what exiting means.
+    #[error("The program exited with code {0}")]
+    Exit(i32),
 }
 
+impl Err {
+    /// The code passed to `exit`, if that's why `err` happened.
+    pub fn exit_code(err: &anyhow::Error) -> Option<i32> {
+        match err.downcast_ref::<Err>() {
+            Some(Err::Exit(code)) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+/// Only for errors that can't be handed back as a `Result`
+/// (trait impls, internal parser bugs). Unwinds instead of
+/// exiting, so an embedding host is never taken down with it.
 #[macro_export]
 macro_rules! panic {
     ($panic_msg: expr) => {
         {
-            println!("Error: {}", $panic_msg);
-            std::process::exit(1)
+            ::std::panic!("Error: {}", $panic_msg)
         }
     };
 }
\ No newline at end of file
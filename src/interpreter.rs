@@ -1,100 +1,144 @@
 use crate::data_types::*;
 use crate::errors::Err;
 use crate::nodes::Node;
+use crate::modules;
+use crate::scope::Scope;
 
-use crate::panic;
-
-use crate::std_lib::{self, construct_lib};
+use crate::std_lib::construct_lib;
 use crate::tokens::Token;
 
-use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
 
 use anyhow::bail;
 
-pub fn interpret(nodes: Vec<Node>) -> anyhow::Result<()> {
-    let mut variables = construct_lib();
-
-    let mut nodes = nodes.into_iter();
+/// A Shrimp interpreter that can be embedded in a Rust program.
+///
+/// Load one or more sources, then call any of their functions.
+/// Console I/O is sandboxed by default: output is discarded and input is
+/// empty, unless [`Interpreter::set_stdout`], [`Interpreter::set_stdin`] or
+/// [`Interpreter::inherit_stdio`] say otherwise.
+pub struct Interpreter {
+    scope: Scope,
+}
 
-    while let Some(node) = nodes.next() {
-        // Insert all functions into the variables.
-        match node {
-            Node::FunctionDecl { name, args, nodes } => {
-                variables.insert(
-                    name.clone(),
-                    Variable::Function(Node::FunctionDecl { name, args, nodes }),
-                );
-            }
-            Node::Term(Token::Ident(id)) => match id.as_str() {
-                "use" => match nodes.next() {
-                    Some(node) => match node {
-                        Node::Term(Token::Ident(lib)) => match lib.as_str() {
-                            "internet" => std_lib::internet(&mut variables),
-                            any => {
-                                bail!(Err::UnknownLib(Node::Term(Token::Ident(any.to_string()))))
-                            }
-                        },
-                        any => bail!(Err::UnknownLib(any)),
-                    },
-                    None => bail!(Err::EOF),
-                },
-                _ => bail!(Err::UnexpectedNode(Some(Node::Term(Token::Ident(id))))),
-            },
-            any => panic!(Err::UnexpectedNode(Some(any))),
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter {
+            scope: Scope::new(construct_lib()),
         }
     }
 
-    let main = variables.remove("main");
+    /// Lexes, parses and registers every function in `source`.
+    /// Relative `use`s are looked up from the working directory.
+    pub fn load(&mut self, source: &str) -> anyhow::Result<()> {
+        modules::register(modules::parse_source(source.as_bytes())?, None, &mut self.scope)
+    }
 
-    if main.is_none() {
-        bail!(Err::NoMain)
+    /// Like [`Interpreter::load`], but relative `use`s are
+    /// looked up next to the file.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        modules::load_file(path.as_ref(), &mut self.scope)
     }
 
-    run(
-        main,
-        &mut variables,
-        vec![Variable::Array(
-            std::env::args().map(|x| Variable::Str(x)).collect(),
-        )],
-        None,
-    )?;
+    /// Makes a Rust closure callable from Shrimp under `name`.
+    pub fn register_fn<F>(&mut self, name: &str, func: F)
+    where
+        F: Fn(Vec<Variable>, &mut Scope) -> anyhow::Result<Variable> + Send + Sync + 'static,
+    {
+        self.scope.insert(name.to_string(), Variable::native(func));
+    }
+
+    /// Sets a variable that Shrimp code can read.
+    pub fn set(&mut self, name: &str, value: Variable) {
+        self.scope.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Variable> {
+        self.scope.lookup(name)
+    }
+
+    /// Names and argument counts of every Shrimp-defined function, sorted by name.
+    pub fn functions(&self) -> Vec<(String, usize)> {
+        let mut out: Vec<(String, usize)> = self
+            .scope
+            .iter()
+            .filter_map(|(name, var)| match var {
+                Variable::Function(Node::FunctionDecl { args, .. }) => {
+                    Some((name.clone(), args.len()))
+                }
+                _ => None,
+            })
+            .collect();
 
-    Ok(())
+        out.sort();
+        out
+    }
+
+    /// Calls a Shrimp (or native) function by name and returns its result.
+    /// If the program calls `exit`, this fails with [`Err::Exit`].
+    pub fn call(&mut self, name: &str, args: Vec<Variable>) -> anyhow::Result<Variable> {
+        let func = match self.scope.lookup(name) {
+            Some(func) => func.clone(),
+            None => bail!(Err::NonexistentVar(name.to_string())),
+        };
+
+        run(Some(func), &mut self.scope, args, None)
+    }
+
+    /// Sends everything the program prints to `out`.
+    pub fn set_stdout(&mut self, out: impl Write + Send + 'static) {
+        self.scope.stdout = Box::new(out);
+    }
+
+    /// Makes `io_input` read from `input`.
+    pub fn set_stdin(&mut self, input: impl BufRead + Send + 'static) {
+        self.scope.stdin = Box::new(input);
+    }
+
+    /// Wires the program up to the real terminal, like the CLI does.
+    pub fn inherit_stdio(&mut self) {
+        self.set_stdout(std::io::stdout());
+        self.set_stdin(BufReader::new(std::io::stdin()));
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 pub fn run(
     func: Option<Variable>,
-    variables: &mut HashMap<String, Variable>,
+    variables: &mut Scope,
     mut args: Vec<Variable>,
     assign_to: Option<String>,
 ) -> anyhow::Result<Variable> {
     let func = match func {
         Some(Variable::Function(func)) => match func {
             Node::FunctionDecl { name, args, nodes } => (name, args, nodes),
-            any => panic!(Err::UnexpectedNode(Some(any))),
+            any => bail!(Err::UnexpectedNode(Some(any))),
         },
         Some(Variable::NativeFunction(exec)) => {
-            let res = exec(args, variables);
-            match assign_to {
-                Some(val) => {
-                    variables.insert(val, res.clone());
-                }
-                None => {}
-            };
+            let res = exec(args, variables)?;
+            if let Some(val) = assign_to {
+                variables.insert(val, res.clone());
+            }
             return Ok(res);
         }
-        Some(any) => panic!(Err::VarTypeMismatch(
+        Some(any) => bail!(Err::VarTypeMismatch(
             Variable::Function(Node::Array(vec![])),
             any
         )),
-        None => panic!(Err::NonexistentVar(
+        None => bail!(Err::NonexistentVar(
             "Name unknown, originated in a function call".to_string()
         )),
     };
 
     if args.len() != func.1.len() {
-        println!("(Error originated in call to {} function)", func.0);
-        panic!(Err::IncorrectArgCount(args.len(), func.1.len()))
+        return Err(anyhow::Error::new(Err::IncorrectArgCount(func.1.len(), args.len()))
+            .context(format!("Error originated in call to the `{}` function", func.0)));
     }
 
     let mut temp_vars = vec![];
@@ -105,22 +149,22 @@ pub fn run(
                 temp_vars.push(id.clone());
                 variables.insert(id, args.remove(0));
             }
-            any => panic!(Err::UnexpectedNode(Some(any))),
+            any => bail!(Err::UnexpectedNode(Some(any))),
         }
     }
 
     macro_rules! arr_into_var {
         ($args: expr) => {
-            Variable::Array(into_var($args, variables))
+            Variable::Array(into_var($args, variables)?)
         };
     }
 
     macro_rules! get_var {
         ($var_name: expr) => {
-            match variables.get(&$var_name) {
+            match variables.lookup(&$var_name) {
                 Some(var) => var.clone(),
                 None => {
-                    panic!(Err::NonexistentVar($var_name));
+                    bail!(Err::NonexistentVar($var_name));
                 }
             }
         };
@@ -135,7 +179,7 @@ pub fn run(
         };
     }
 
-    let mut func = func.2.into_iter();
+    let mut func = func.2.into_iter().peekable();
 
     while let Some(node) = func.next() {
         match node {
@@ -144,7 +188,7 @@ pub fn run(
                     "decl" => match func.next() {
                         Some(Node::Term(Token::Ident(var))) => match func.next() {
                             Some(tok) => {
-                                let res = into_var(vec![tok], variables).remove(0);
+                                let res = into_var(vec![tok], variables)?.remove(0);
                                 variables.insert(var, res);
                             }
                             any => bail!(Err::UnexpectedNode(any)),
@@ -155,36 +199,27 @@ pub fn run(
                         Some(Node::Term(Token::Ident(id))) => {
                             variables.remove(&id);
                         }
-                        None => panic!(Err::EOF),
-                        any => panic!(Err::UnexpectedNode(any)),
+                        None => bail!(Err::EOF),
+                        any => bail!(Err::UnexpectedNode(any)),
                     },
                     "return" => match func.next() {
                         Some(Node::Term(tok)) => match tok {
                             Token::Ident(id) => return Ok(get_var!(id)),
-                            any => return Ok(any.as_var()),
+                            any => return any.as_var(),
                         },
                         Some(Node::Array(arr)) => return Ok(arr_into_var!(arr)),
                         any => bail!(Err::UnexpectedNode(any)),
                     },
                     "use" => match func.next() {
-                        Some(node) => match node {
-                            Node::Term(Token::Ident(lib)) => match lib.as_str() {
-                                "io" => std_lib::io(variables),
-                                "internet" => std_lib::internet(variables),
-                                "html" => std_lib::html(variables),
-                                any => {
-                                    bail!(Err::UnknownLib(Node::Term(Token::Ident(
-                                        any.to_string()
-                                    ))))
-                                }
-                            },
-                            any => bail!(Err::UnknownLib(any)),
-                        },
+                        Some(node) => {
+                            let alias = modules::take_alias(&mut func)?;
+                            modules::import(node, alias, None, variables)?
+                        }
                         None => bail!(Err::EOF),
                     },
                     "if" => match func.next() {
                         Some(thing) => {
-                            match into_var(vec![thing], variables).remove(0) {
+                            match into_var(vec![thing], variables)?.remove(0) {
                                 Variable::Bool(boolean) => {
                                     if boolean {
                                         run(
@@ -197,7 +232,7 @@ pub fn run(
                                                     // nested loops/ifs
                                                     Some(Node::Group(arr)) => arr,
                                                     any => {
-                                                        panic!(Err::UnexpectedNode(any))
+                                                        bail!(Err::UnexpectedNode(any))
                                                     }
                                                 },
                                             })),
@@ -216,66 +251,136 @@ pub fn run(
                                             Some(Node::Group(_)) => {}
                                             // Proper syntax/happy path
                                             Some(Node::Block(_)) => {}
-                                            any => panic!(Err::UnexpectedNode(any)),
+                                            any => bail!(Err::UnexpectedNode(any)),
                                         }
                                     }
                                 }
-                                any => panic!(Err::VarTypeMismatch(Variable::Bool(true), any)),
+                                any => bail!(Err::VarTypeMismatch(Variable::Bool(true), any)),
                             }
                         }
-                        None => panic!(Err::EOF),
+                        None => bail!(Err::EOF),
                     },
                     "for" => match func.next() {
                         Some(node) => {
-                            match into_var(vec![node], variables).remove(0) {
-                                Variable::Array(arr) => match func.next() {
-                                    Some(Node::Term(Token::ForAssigner)) => match func.next() {
-                                        Some(Node::Term(Token::Ident(id))) => {
-                                            let block = match func.next() {
-                                                Some(Node::Block(block)) => block,
-                                                // FIXME: Hacky workaround for nested
-                                                // loops.
-                                                Some(Node::Group(block)) => block,
-                                                None => panic!(Err::EOF),
-                                                any => panic!(Err::UnexpectedNode(any)),
-                                            };
-
-                                            for var in arr {
-                                                variables.insert(id.clone(), var);
-                                                run(
-                                                    Some(Variable::Function(Node::FunctionDecl {
-                                                        name: "for loop".to_string(),
-                                                        args: vec![],
-                                                        nodes: block.clone(),
-                                                    })),
-                                                    variables,
-                                                    args.clone(),
-                                                    assign_to.clone(),
-                                                )?;
-                                            }
-                                        }
-                                        None => panic!(Err::EOF),
-                                        any => {
-                                            panic!(Err::UnexpectedNode(any))
+                            let items: LazyIter = match into_var(vec![node], variables)?.remove(0) {
+                                Variable::Array(arr) => Box::new(arr.into_iter().map(Ok)),
+                                // Iterator handles (e.g. `lines(file)`) are pulled
+                                // from one item at a time, instead of all at once.
+                                Variable::Handle(handle) => Box::new(std::iter::from_fn(move || {
+                                    handle.next_item().transpose()
+                                })),
+                                any => bail!(Err::VarTypeMismatch(Variable::Array(vec![]), any)),
+                            };
+
+                            match func.next() {
+                                Some(Node::Term(Token::ForAssigner)) => match func.next() {
+                                    Some(Node::Term(Token::Ident(id))) => {
+                                        let block = match func.next() {
+                                            Some(Node::Block(block)) => block,
+                                            // FIXME: Hacky workaround for nested
+                                            // loops.
+                                            Some(Node::Group(block)) => block,
+                                            None => bail!(Err::EOF),
+                                            any => bail!(Err::UnexpectedNode(any)),
+                                        };
+
+                                        for var in items {
+                                            variables.insert(id.clone(), var?);
+                                            run(
+                                                Some(Variable::Function(Node::FunctionDecl {
+                                                    name: "for loop".to_string(),
+                                                    args: vec![],
+                                                    nodes: block.clone(),
+                                                })),
+                                                variables,
+                                                args.clone(),
+                                                assign_to.clone(),
+                                            )?;
                                         }
-                                    },
-                                    None => panic!(Err::EOF),
-                                    any => panic!(Err::TypeMismatch(Node::Array(vec![]), any)),
+                                    }
+                                    None => bail!(Err::EOF),
+                                    any => {
+                                        bail!(Err::UnexpectedNode(any))
+                                    }
                                 },
-                                any => panic!(Err::VarTypeMismatch(Variable::Array(vec![]), any)),
+                                None => bail!(Err::EOF),
+                                any => bail!(Err::TypeMismatch(Node::Array(vec![]), any)),
                             }
                         }
-                        None => panic!(Err::EOF),
+                        None => bail!(Err::EOF),
                     },
+                    // try { ... } catch err { ... }
+                    // If anything in the first block fails, the second block is run
+                    // with the error message stored in `err`.
+                    "try" => {
+                        let try_block = match func.next() {
+                            Some(Node::Block(block)) => block,
+                            // FIXME: Hacky workaround for nested
+                            // loops.
+                            Some(Node::Group(block)) => block,
+                            None => bail!(Err::EOF),
+                            any => bail!(Err::UnexpectedNode(any)),
+                        };
+
+                        match func.next() {
+                            Some(Node::Term(Token::Ident(id))) if id == "catch" => {}
+                            None => bail!(Err::EOF),
+                            any => bail!(Err::UnexpectedNode(any)),
+                        };
+
+                        let err_name = match func.next() {
+                            Some(Node::Term(Token::Ident(id))) => id,
+                            None => bail!(Err::EOF),
+                            any => bail!(Err::UnexpectedNode(any)),
+                        };
+
+                        let catch_block = match func.next() {
+                            Some(Node::Block(block)) => block,
+                            Some(Node::Group(block)) => block,
+                            None => bail!(Err::EOF),
+                            any => bail!(Err::UnexpectedNode(any)),
+                        };
+
+                        let res = run(
+                            Some(Variable::Function(Node::FunctionDecl {
+                                name: "try block".to_string(),
+                                args: vec![],
+                                nodes: try_block,
+                            })),
+                            variables,
+                            args.clone(),
+                            assign_to.clone(),
+                        );
+
+                        if let Err(err) = res {
+                            // `exit` isn't a failure, so it can't be caught.
+                            if Err::exit_code(&err).is_some() {
+                                return Err(err);
+                            }
+
+                            variables.insert(err_name, Variable::Str(format!("{:#}", err)));
+
+                            run(
+                                Some(Variable::Function(Node::FunctionDecl {
+                                    name: "catch block".to_string(),
+                                    args: vec![],
+                                    nodes: catch_block,
+                                })),
+                                variables,
+                                args.clone(),
+                                assign_to.clone(),
+                            )?;
+                        }
+                    }
                     "while" => match func.next() {
                         Some(node) => {
                             let block = match func.next() {
                                 Some(Node::Block(block)) => block,
-                                None => panic!(Err::EOF),
-                                any => panic!(Err::UnexpectedNode(any)),
+                                None => bail!(Err::EOF),
+                                any => bail!(Err::UnexpectedNode(any)),
                             };
 
-                            while into_var(vec![node.clone()], variables).remove(0)
+                            while into_var(vec![node.clone()], variables)?.remove(0)
                                 == Variable::Bool(true)
                             {
                                 run(
@@ -290,10 +395,10 @@ pub fn run(
                                 )?;
                             }
                         }
-                        None => panic!(Err::EOF),
+                        None => bail!(Err::EOF),
                     },
                     any => {
-                        panic!(Err::UnknownKeyword(any.to_string()))
+                        bail!(Err::UnknownKeyword(any.to_string()))
                     }
                 };
             }
@@ -302,54 +407,60 @@ pub fn run(
                 args,
                 assign_to,
             } => {
-                match variables.clone().get(&name) {
+                match variables.lookup(&name).cloned() {
                     Some(Variable::Function(fn_decl)) => {
-                        let res = into_var(args, variables);
+                        let res = into_var(args, variables)?;
 
-                        run(
-                            Some(Variable::Function(fn_decl.clone())),
+                        let res = run(
+                            Some(Variable::Function(fn_decl)),
                             variables,
                             res,
-                            assign_to,
+                            assign_to.clone(),
                         )?;
+
+                        if let Some(string) = assign_to {
+                            *(get_var!(g_mut => string)) = res;
+                        }
                     }
                     Some(Variable::NativeFunction(func)) => match assign_to {
                         Some(string) => {
-                            let res = into_var(args, variables);
+                            let res = into_var(args, variables)?;
 
-                            *(get_var!(g_mut => string)) = func(res, variables);
+                            *(get_var!(g_mut => string)) = func(res, variables)?;
                         }
                         None => {
-                            let res = into_var(args, variables);
-                            func(res, variables);
+                            let res = into_var(args, variables)?;
+                            func(res, variables)?;
                         }
                     },
                     None => {
                         if name == "if" {
-                            println!("Don't put a group (any tokens enclosed in brackets) after an if statement!");
+                            return Err(anyhow::Error::new(Err::NonexistentVar(name)).context(
+                                "Don't put a group (any tokens enclosed in brackets) after an if statement!",
+                            ));
                         }
-                        panic!(Err::NonexistentVar(name))
+                        bail!(Err::NonexistentVar(name))
                     }
-                    any => panic!(Err::VarTypeMismatch(
+                    Some(any) => bail!(Err::VarTypeMismatch(
                         Variable::Function(Node::Array(vec![])),
-                        any.unwrap().clone()
+                        any
                     )),
                 };
             }
-            any => panic!(Err::UnexpectedNode(Some(any))),
+            any => bail!(Err::UnexpectedNode(Some(any))),
         }
     }
 
     Ok(Variable::Void)
 }
 
-pub fn into_var(args: Vec<Node>, variables: &mut HashMap<String, Variable>) -> Vec<Variable> {
+pub fn into_var(args: Vec<Node>, variables: &mut Scope) -> anyhow::Result<Vec<Variable>> {
     macro_rules! get_var {
         ($var_name: expr) => {
-            match variables.get(&$var_name) {
+            match variables.lookup(&$var_name) {
                 Some(var) => var.clone(),
                 None => {
-                    panic!(Err::NonexistentVar($var_name));
+                    bail!(Err::NonexistentVar($var_name));
                 }
             }
         };
@@ -357,27 +468,24 @@ pub fn into_var(args: Vec<Node>, variables: &mut HashMap<String, Variable>) -> V
             match variables.get_mut(&$var_name) {
                 Some(var) => var,
                 None => {
-                    panic!(Err::NonexistentVar($var_name));
+                    bail!(Err::NonexistentVar($var_name));
                 }
             }
         };
     }
 
     args.into_iter()
-        .map(|x| match x {
+        .map(|x| Ok(match x {
             Node::Term(Token::Ident(var)) => get_var!(var),
             Node::CallExpr {
                 name,
                 args,
                 assign_to,
             } => {
-                let res = into_var(args, variables);
-                match run(Some(get_var!(name)), variables, res, assign_to) {
-                    Ok(res) => res,
-                    Err(err) => panic!(err),
-                }
+                let res = into_var(args, variables)?;
+                run(Some(get_var!(name)), variables, res, assign_to)?
             }
-            any => any.as_var(),
-        })
+            any => any.as_var()?,
+        }))
         .collect()
 }
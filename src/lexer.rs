@@ -1,4 +1,3 @@
-use std::fs::File;
 use std::io::Read;
 
 use anyhow::bail;
@@ -7,10 +6,9 @@ use anyhow::Result;
 use super::errors::Err;
 use super::tokens::Token;
 
-use crate::panic;
-
-// Generate all tokens for the parser to make an AST
-pub fn make_tokens(mut file: File) -> Result<Vec<Token>> {
+// Generate all tokens for the parser to make an AST.
+// Takes anything readable, so source can come from a file or a string.
+pub fn make_tokens(mut file: impl Read) -> Result<Vec<Token>> {
     // Track line position for use in error messages
     let mut line_pos: usize = 0;
     let mut line_num: usize = 0;
@@ -20,7 +18,7 @@ pub fn make_tokens(mut file: File) -> Result<Vec<Token>> {
     file.read_to_string(&mut chars)?;
 
     // Iterator to go through all chars.
-    let mut char_list = chars.chars().into_iter().peekable();
+    let mut char_list = chars.chars().peekable();
 
     // Free memory, drops file handle because it will
     // never be used again. May be useful for Windows users?
@@ -58,9 +56,11 @@ pub fn make_tokens(mut file: File) -> Result<Vec<Token>> {
 
                 // Loop will continue pushing all chars to out until
                 // a non ascii-alphanumeric character is found.
+                // `.` is allowed so that namespaces (`internet.get`) are
+                // a single identifier.
                 while let Some(code) = char_list.peek() {
                     // Break loop if code is not alphanumeric
-                    if !code.is_ascii_alphanumeric() && *code != '_' {
+                    if !code.is_ascii_alphanumeric() && *code != '_' && *code != '.' {
                         break;
                     }
                     out.push(char_list.next().unwrap());
@@ -81,7 +81,7 @@ pub fn make_tokens(mut file: File) -> Result<Vec<Token>> {
                     if !code.is_numeric() {
                         // If number is a float,
                         // add '.' to be parsed.
-                        if float == false && *code == '.' {
+                        if !float && *code == '.' {
                             float = true;
                             out.push(char_list.next().unwrap());
                             continue;
@@ -110,8 +110,8 @@ pub fn make_tokens(mut file: File) -> Result<Vec<Token>> {
             ';' => final_out.push(Token::EndLine),
             '=' => match char_list.next() {
                 Some('>') => final_out.push(Token::ForAssigner),
-                Some(any) => panic!(Err::UnexpectedChar(line_num, line_pos, any)),
-                None => panic!(Err::EOF),
+                Some(any) => bail!(Err::UnexpectedChar(line_num, line_pos, any)),
+                None => bail!(Err::EOF),
             },
             // Handles arrow assigners
             '-' => match char_list.next() {
@@ -130,7 +130,7 @@ pub fn make_tokens(mut file: File) -> Result<Vec<Token>> {
                             if !code.is_numeric() {
                                 // If number is a float,
                                 // add '.' to be parsed.
-                                if float == false && *code == '.' {
+                                if !float && *code == '.' {
                                     float = true;
                                     out.push(char_list.next().unwrap());
                                     continue;
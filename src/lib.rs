@@ -0,0 +1,29 @@
+
+//! ShrimpLang 3, as a library.
+//!
+//! ```ignore
+//! use shrimp::{Interpreter, Variable};
+//!
+//! let mut shrimp = Interpreter::new();
+//! shrimp.register_fn("host_name", |_, _| Ok(Variable::Str("my-service".into())));
+//! shrimp.load("@greet(name) { con(\"Hello \" name) -> out return out }")?;
+//!
+//! let greeting = shrimp.call("greet", vec![Variable::Str("Shrimp".into())])?;
+//! ```
+
+pub mod data_types;
+pub mod errors;
+pub mod interpreter;
+pub mod lexer;
+pub mod modules;
+pub mod nodes;
+pub mod parser;
+pub mod scope;
+pub mod std_lib;
+pub mod test_runner;
+pub mod tokens;
+
+pub use data_types::Variable;
+pub use errors::Err;
+pub use interpreter::Interpreter;
+pub use scope::{Scope, SharedBuffer};
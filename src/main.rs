@@ -1,22 +1,16 @@
-#![feature(let_chains)]
-
 use std::fs::File;
 
+use anyhow::bail;
 use anyhow::Result;
 use clap::Parser;
 
-mod data_types;
-mod errors;
-mod interpreter;
-mod lexer;
-mod nodes;
-mod parser;
-mod std_lib;
-mod tokens;
+use shrimp::{lexer, test_runner, Err, Interpreter, Variable};
 
 #[derive(Debug, Parser)]
 #[clap(version = "1.0", author = "T-O-R-U-S <bageliq@protonmail.com>")]
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
     /// The Shrimp file to execute
     file: Option<String>,
     /// Boolean -- decides whether to display the tokens or not.
@@ -25,28 +19,62 @@ struct Cli {
     display_tokens: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Debug, Parser)]
+enum Command {
+    /// Runs every `@test_...()` function in the given files
+    Test {
+        /// The Shrimp files to test
+        #[clap(required = true)]
+        files: Vec<String>,
+    },
+}
+
+fn main() -> Result<()> {
     let args = Cli::parse();
 
-    // TODO: Use tokens in meaningful way.
-    let tokens = lexer::make_tokens(
-        File::open(args.file.unwrap_or(String::from("main.imp")).as_str())
-            .expect("Failed to open file. (Does it exist?)"),
-    )?;
+    if let Some(Command::Test { files }) = args.command {
+        if !test_runner::run_files(&files, &mut std::io::stdout())? {
+            std::process::exit(1)
+        }
+        return Ok(());
+    }
+
+    let file = args.file.unwrap_or(String::from("main.imp"));
 
     if args.display_tokens {
+        let tokens = lexer::make_tokens(
+            File::open(file.as_str()).expect("Failed to open file. (Does it exist?)"),
+        )?;
+
         println!("TOKENS:");
         println!("{:?}", tokens);
         println!("END TOKENS");
     }
-    let nodes = parser::parse(tokens)?;
 
-    let nodes = parser::make_fn(nodes)?;
+    let mut interpreter = Interpreter::new();
+
+    // The CLI is the one place where I/O should
+    // actually reach the terminal.
+    interpreter.inherit_stdio();
 
-    let nodes = parser::make_fn_call(nodes)?;
+    interpreter.load_file(&file)?;
 
-    interpreter::interpret(nodes)?;
+    if interpreter.get("main").is_none() {
+        bail!(Err::NoMain)
+    }
 
-    Ok(())
+    let res = interpreter.call(
+        "main",
+        vec![Variable::Array(
+            std::env::args().map(Variable::Str).collect(),
+        )],
+    );
+
+    match res {
+        Err(err) => match Err::exit_code(&err) {
+            Some(code) => std::process::exit(code),
+            None => Err(err),
+        },
+        Ok(_) => Ok(()),
+    }
 }
@@ -0,0 +1,256 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use anyhow::Context;
+
+use crate::data_types::Variable;
+use crate::errors::Err;
+use crate::nodes::Node;
+use crate::scope::Scope;
+use crate::std_lib;
+use crate::tokens::Token;
+use crate::{lexer, parser};
+
+/// Remembers which Shrimp files have been loaded, so that each
+/// one is only ever loaded once and import cycles can be caught.
+#[derive(Default)]
+pub struct Modules {
+    /// Canonical paths of every file that has finished loading.
+    loaded: HashSet<PathBuf>,
+    /// Files that are part-way through loading, innermost last.
+    loading: Vec<PathBuf>,
+}
+
+/// Runs the whole lexer -> parser pipeline over a source.
+pub fn parse_source(source: impl Read) -> anyhow::Result<Vec<Node>> {
+    let tokens = lexer::make_tokens(source)?;
+
+    let nodes = parser::parse(tokens)?;
+
+    let nodes = parser::make_fn(nodes)?;
+
+    parser::make_fn_call(nodes)
+}
+
+/// Loads a Shrimp file into the scope, unless it already has been.
+pub fn load_file(path: &Path, scope: &mut Scope) -> anyhow::Result<()> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open {}. (Does it exist?)", path.display()))?;
+
+    let canonical = path.canonicalize()?;
+
+    if scope.modules.loading.contains(&canonical) {
+        let mut chain: Vec<String> = scope
+            .modules
+            .loading
+            .iter()
+            .skip_while(|x| **x != canonical)
+            .map(|x| x.display().to_string())
+            .collect();
+        chain.push(canonical.display().to_string());
+
+        bail!(Err::ImportCycle(chain.join(" -> ")))
+    }
+
+    if scope.modules.loaded.contains(&canonical) {
+        return Ok(());
+    }
+
+    scope.modules.loading.push(canonical.clone());
+
+    let res = parse_source(file)
+        .and_then(|nodes| register(nodes, canonical.parent(), scope))
+        .with_context(|| format!("Error originated in {}", path.display()));
+
+    scope.modules.loading.pop();
+    res?;
+
+    scope.modules.loaded.insert(canonical);
+
+    Ok(())
+}
+
+/// Registers every function declared at the top level, and runs
+/// any top-level `use`s. `dir` is where relative imports are looked up.
+pub fn register(nodes: Vec<Node>, dir: Option<&Path>, scope: &mut Scope) -> anyhow::Result<()> {
+    let mut nodes = nodes.into_iter().peekable();
+
+    while let Some(node) = nodes.next() {
+        // Insert all functions into the variables.
+        match node {
+            Node::FunctionDecl { name, args, nodes } => {
+                scope.insert(
+                    name.clone(),
+                    Variable::Function(Node::FunctionDecl {
+                        name,
+                        args,
+                        nodes: resolve_uses(nodes, dir),
+                    }),
+                );
+            }
+            Node::Term(Token::Ident(id)) => match id.as_str() {
+                "use" => match nodes.next() {
+                    Some(node) => {
+                        let alias = take_alias(&mut nodes)?;
+                        import(node, alias, dir, scope)?
+                    }
+                    None => bail!(Err::EOF),
+                },
+                _ => bail!(Err::UnexpectedNode(Some(Node::Term(Token::Ident(id))))),
+            },
+            any => bail!(Err::UnexpectedNode(Some(any))),
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles every form of `use`:
+/// - `use internet`, which binds the library's namespace (`internet.get`)
+///   along with its deprecated prefixed names (`internet_get`)
+/// - `use internet as net`, which binds the namespace under another name
+/// - `use io (read write)`, which binds the listed members directly
+/// - `use utils` and `use "path/to/utils.imp"`, which load a Shrimp file
+pub fn import(
+    node: Node,
+    alias: Option<String>,
+    dir: Option<&Path>,
+    scope: &mut Scope,
+) -> anyhow::Result<()> {
+    let path = match node {
+        Node::Term(Token::Ident(lib)) => {
+            if let Some(library) = std_lib::library(&lib) {
+                match alias {
+                    Some(alias) => {
+                        scope.insert(alias, Variable::Map(library.members));
+                    }
+                    None => {
+                        if let Some((prefix, names)) = library.legacy {
+                            for name in names {
+                                if let Some(member) = library.members.get(*name) {
+                                    scope.insert(prefix.to_string() + name, member.clone());
+                                }
+                            }
+                        }
+                        scope.insert(lib, Variable::Map(library.members));
+                    }
+                }
+                return Ok(());
+            }
+
+            let file = format!("{}.imp", lib);
+            resolve(&file, dir).ok_or(Err::ModuleNotFound(file))?
+        }
+        // `use io (read write)` has already been mistaken
+        // for a call by the parser.
+        Node::CallExpr { name, args, .. } => {
+            let mut library = match std_lib::library(&name) {
+                Some(library) => library,
+                None => bail!(Err::UnknownLib(Node::Term(Token::Ident(name)))),
+            };
+
+            for arg in args {
+                match arg {
+                    Node::Term(Token::Ident(member)) => match library.members.remove(&member) {
+                        Some(var) => {
+                            scope.insert(member, var);
+                        }
+                        None => bail!(Err::NonexistentVar(format!("{}.{}", name, member))),
+                    },
+                    any => bail!(Err::TypeMismatch(
+                        Node::Term(Token::Ident("identifier".to_string())),
+                        Some(any)
+                    )),
+                }
+            }
+
+            return Ok(());
+        }
+        Node::Term(Token::Str(path)) => {
+            resolve(&path, dir).ok_or(Err::ModuleNotFound(path.clone()))?
+        }
+        any => bail!(Err::UnknownLib(any)),
+    };
+
+    if let Some(alias) = alias {
+        bail!(Err::UnsupportedImport(format!(
+            "`as {}` only works with built-in libraries",
+            alias
+        )))
+    }
+
+    load_file(&path, scope)
+}
+
+/// Consumes an `as <name>` following a `use`, if there is one.
+pub fn take_alias(nodes: &mut Peekable<impl Iterator<Item = Node>>) -> anyhow::Result<Option<String>> {
+    if nodes.peek() != Some(&Node::Term(Token::Ident("as".to_string()))) {
+        return Ok(None);
+    }
+    nodes.next();
+
+    match nodes.next() {
+        Some(Node::Term(Token::Ident(alias))) => Ok(Some(alias)),
+        any => bail!(Err::UnexpectedNode(any)),
+    }
+}
+
+/// Looks for a module next to the importing file first, then
+/// in every directory listed in `SHRIMP_PATH`.
+pub fn resolve(path: &str, dir: Option<&Path>) -> Option<PathBuf> {
+    let path = Path::new(path);
+
+    if path.is_absolute() {
+        return Some(path.to_path_buf()).filter(|x| x.is_file());
+    }
+
+    let mut search = vec![dir.map(Path::to_path_buf).unwrap_or_default()];
+
+    if let Some(shrimp_path) = std::env::var_os("SHRIMP_PATH") {
+        search.extend(std::env::split_paths(&shrimp_path));
+    }
+
+    search.into_iter().map(|x| x.join(path)).find(|x| x.is_file())
+}
+
+/// `use` inside a function body only runs when the function is called,
+/// by which point nobody remembers which file it came from. So module
+/// paths are pinned down here, while the importing file is still known.
+fn resolve_uses(nodes: Vec<Node>, dir: Option<&Path>) -> Vec<Node> {
+    let mut out = Vec::with_capacity(nodes.len());
+
+    let mut nodes = nodes.into_iter();
+
+    while let Some(node) = nodes.next() {
+        match node {
+            Node::Term(Token::Ident(id)) if id == "use" => {
+                out.push(Node::Term(Token::Ident(id)));
+
+                let target = match nodes.next() {
+                    Some(Node::Term(Token::Str(path))) => resolve(&path, dir)
+                        .map(|x| Node::Term(Token::Str(x.display().to_string())))
+                        .unwrap_or(Node::Term(Token::Str(path))),
+                    Some(Node::Term(Token::Ident(lib))) if !std_lib::is_builtin(&lib) => {
+                        match resolve(&format!("{}.imp", lib), dir) {
+                            Some(x) => Node::Term(Token::Str(x.display().to_string())),
+                            None => Node::Term(Token::Ident(lib)),
+                        }
+                    }
+                    Some(any) => any,
+                    None => break,
+                };
+
+                out.push(target);
+            }
+            Node::Block(block) => out.push(Node::Block(resolve_uses(block, dir))),
+            Node::Group(group) => out.push(Node::Group(resolve_uses(group, dir))),
+            any => out.push(any),
+        }
+    }
+
+    out
+}
@@ -1,7 +1,6 @@
 use crate::data_types::Variable;
 use crate::tokens::Token;
 use crate::errors::Err;
-use crate::panic;
 
 #[derive(Debug, PartialEq)]
 /// This file is responsible for all nodes in the AST
@@ -27,9 +26,9 @@ pub enum Node {
         assign_to: Option<String>,
     },
     /// Any function declaration. The typically, last part to be parsed.
-    /// ```
+    /// ```text
     /// @main() {
-    /// 	print("Hello world!");
+    ///     print("Hello world!");
     /// }
     /// ```
     FunctionDecl {
@@ -42,24 +41,28 @@ pub enum Node {
 impl Node {
     pub fn as_words(&self) -> String {
         match self {
-            Node::Term(tok) => format!("{}", tok.as_words()),
+            Node::Term(tok) => tok.as_words().to_string(),
             Node::Array(_) => "an array".to_string(),
             Node::Group(_) => "a group".to_string(),
             // Don't know what i was thinking when i wrote this,
             // but i'm too lazy to change the rest into
             // "a codeblock".into_string()
             Node::Block(_) => "a codeblock".to_string(),
-            Node::CallExpr { .. } => format!("{}", "a call expression"),
-            Node::FunctionDecl { .. } => format!("{}", "a function declaration"),
+            Node::CallExpr { .. } => "a call expression".to_string(),
+            Node::FunctionDecl { .. } => "a function declaration".to_string(),
         }
     }
-    pub fn as_var(self) -> Variable {
-        match self {
-            Node::Array(arr) => Variable::Array(arr.into_iter().map(|x| x.as_var()).collect()),
+    pub fn as_var(self) -> anyhow::Result<Variable> {
+        Ok(match self {
+            Node::Array(arr) => Variable::Array(
+                arr.into_iter()
+                    .map(|x| x.as_var())
+                    .collect::<anyhow::Result<_>>()?,
+            ),
             Node::Term(Token::Num(num)) => Variable::Num(num),
             Node::Term(Token::Str(string)) => Variable::Str(string),
-            any => panic!(Err::UnexpectedNode(Some(any))),
-        }
+            any => anyhow::bail!(Err::UnexpectedNode(Some(any))),
+        })
     }
 }
 
@@ -132,12 +132,32 @@ pub fn parse(tokens: Vec<Token>) -> anyhow::Result<Vec<Node>> {
 pub fn make_fn(nodes: Vec<Node>) -> anyhow::Result<Vec<Node>> {
     let mut final_out = Vec::new();
 
-    let mut node_list = nodes.into_iter();
+    let mut node_list = nodes.into_iter().peekable();
 
     while let Some(node) = node_list.next() {
         let mut name = None;
         let mut args = None;
         let mut nodes = None;
+        // Top-level `use`s are kept, along with whatever they import,
+        // e.g. `use io (read write)` or `use internet as net`.
+        if node == Node::Term(Token::Ident("use".to_string())) {
+            final_out.push(node);
+            match node_list.next() {
+                Some(lib) => final_out.push(lib),
+                None => bail!(Err::EOF),
+            }
+            if let Some(Node::Group(_)) = node_list.peek() {
+                final_out.push(node_list.next().unwrap());
+            }
+            if node_list.peek() == Some(&Node::Term(Token::Ident("as".to_string()))) {
+                final_out.push(node_list.next().unwrap());
+                match node_list.next() {
+                    Some(alias) => final_out.push(alias),
+                    None => bail!(Err::EOF),
+                }
+            }
+            continue;
+        }
         if node != Node::Term(Token::FunctionDecl) {
             continue;
         }
@@ -212,7 +232,7 @@ pub fn make_fn_call(nodes: Vec<Node>) -> anyhow::Result<Vec<Node>> {
             continue;
         } else if let Node::Term(Token::Ident(id)) = node.clone()
             && let Some(Node::Array(idx)) = nodes.peek() {
-                if idx.len() == 1 && let Some(Node::Term(Token::Num(num))) = idx.get(0) {
+                if idx.len() == 1 && let Some(Node::Term(Token::Num(num))) = idx.first() {
                     final_out.push(
                         Node::CallExpr {
                             name: "index".into(),
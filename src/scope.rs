@@ -0,0 +1,118 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use rand::rngs::StdRng;
+
+use crate::data_types::Variable;
+use crate::modules::Modules;
+
+/// Everything a running Shrimp program can see.
+///
+/// Derefs to the variable map, so it can be used anywhere the
+/// plain `HashMap<String, Variable>` used to be.
+pub struct Scope {
+    variables: HashMap<String, Variable>,
+    /// Where `print`, `println` and friends write to.
+    /// Discards everything unless the host asks otherwise.
+    pub stdout: Box<dyn Write + Send>,
+    /// Where `io_input` reads from. Empty unless the host asks otherwise.
+    pub stdin: Box<dyn BufRead + Send>,
+    /// Every Shrimp file that has been `use`d so far.
+    pub modules: Modules,
+    /// The generator `random` falls back to. Created when it's first
+    /// needed, so that each program can seed its own.
+    pub(crate) rng: Option<StdRng>,
+    /// Environment variables set (`Some`) or removed (`None`) by
+    /// `os.set_env` and `os.remove_env`. The process's own environment
+    /// is shared with the host, so it is left alone.
+    pub(crate) env: BTreeMap<String, Option<String>>,
+}
+
+impl Scope {
+    pub fn new(variables: HashMap<String, Variable>) -> Self {
+        Scope {
+            variables,
+            stdout: Box::new(std::io::sink()),
+            stdin: Box::new(std::io::empty()),
+            modules: Modules::default(),
+            rng: None,
+            env: BTreeMap::new(),
+        }
+    }
+
+    /// Gets a variable by name. Names with dots in them (`internet.get`)
+    /// are looked up inside maps, unless a variable has that exact name.
+    pub fn lookup(&self, name: &str) -> Option<&Variable> {
+        if let Some(var) = self.variables.get(name) {
+            return Some(var);
+        }
+
+        let mut path = name.split('.');
+        let mut current = self.variables.get(path.next()?)?;
+
+        for key in path {
+            current = match current {
+                Variable::Map(map) => map.get(key)?,
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+}
+
+impl Deref for Scope {
+    type Target = HashMap<String, Variable>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.variables
+    }
+}
+
+impl DerefMut for Scope {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.variables
+    }
+}
+
+/// An in-memory stdout that can still be read after it
+/// has been handed to an interpreter.
+///
+/// ```ignore
+/// let out = SharedBuffer::new();
+/// shrimp.set_stdout(out.clone());
+/// shrimp.call("main", vec![])?;
+/// assert_eq!(out.contents(), "Hello world!\n");
+/// ```
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    pub fn new() -> Self {
+        SharedBuffer::default()
+    }
+
+    /// Everything written so far, lossily decoded as UTF-8.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+
+    /// Empties the buffer, returning what was in it.
+    pub fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.0.lock().unwrap());
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
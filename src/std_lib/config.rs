@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use toml::Value;
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+use anyhow::Context;
+
+use super::{as_map, as_str, expect_args};
+
+/// Where in a config something is, for error messages.
+fn location(file: Option<&str>, line: usize) -> String {
+    match file {
+        Some(file) => format!("{}, line {}", file, line),
+        None => format!("line {}", line),
+    }
+}
+
+/// Tables become maps. Dates and times are kept as strings.
+fn from_toml(value: Value) -> Variable {
+    match value {
+        Value::String(string) => Variable::Str(string),
+        Value::Integer(int) => Variable::Num(int as f64),
+        Value::Float(float) => Variable::Num(float),
+        Value::Boolean(b) => Variable::Bool(b),
+        Value::Datetime(datetime) => Variable::Str(datetime.to_string()),
+        Value::Array(arr) => Variable::Array(arr.into_iter().map(from_toml).collect()),
+        Value::Table(table) => Variable::Map(
+            table
+                .into_iter()
+                .map(|(key, x)| (key, from_toml(x)))
+                .collect(),
+        ),
+    }
+}
+
+fn to_toml(var: &Variable) -> anyhow::Result<Value> {
+    Ok(match var {
+        Variable::Str(string) | Variable::Html(string) => Value::String(string.clone()),
+        Variable::Num(num) if num.fract() == 0.0 && num.abs() < i64::MAX as f64 => {
+            Value::Integer(*num as i64)
+        }
+        Variable::Num(num) => Value::Float(*num),
+        Variable::Bool(b) => Value::Boolean(*b),
+        Variable::Array(arr) => {
+            Value::Array(arr.iter().map(to_toml).collect::<anyhow::Result<_>>()?)
+        }
+        Variable::Map(map) => {
+            let mut table = toml::map::Map::new();
+            for (key, x) in map {
+                // TOML has nothing for void, so it's left out.
+                if !matches!(x, Variable::Void) {
+                    table.insert(key.clone(), to_toml(x)?);
+                }
+            }
+            Value::Table(table)
+        }
+        any => bail!(Err::Unserializable(any.as_words(), "TOML")),
+    })
+}
+
+fn parse_toml(text: &str, file: Option<&str>) -> anyhow::Result<Variable> {
+    match text.parse::<Value>() {
+        Ok(value) => Ok(from_toml(value)),
+        Err(err) => {
+            // toml puts the position on the end of its messages.
+            let message = err.to_string();
+            let message = match message.rfind(" at line ") {
+                Some(idx) => message[..idx].to_string(),
+                None => message,
+            };
+
+            let line = err.line_col().map(|(line, _)| line + 1).unwrap_or(1);
+
+            bail!(Err::BadConfig("TOML", location(file, line), message))
+        }
+    }
+}
+
+/// INI has no one standard, so this takes the common parts: `[section]`s,
+/// `key = value` (or `key: value`), and comments starting with `;` or `#`.
+/// Every value is a string.
+fn parse_ini(text: &str, file: Option<&str>) -> anyhow::Result<Variable> {
+    let mut root = BTreeMap::new();
+    // Keys before the first section go at the top.
+    let mut section: Option<String> = None;
+
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let bad =
+            |message: &str| Err::BadConfig("INI", location(file, idx + 1), message.to_string());
+
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = match name.strip_suffix(']') {
+                Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+                _ => bail!(bad("Expected `[section]`")),
+            };
+
+            match root
+                .entry(name.clone())
+                .or_insert_with(|| Variable::Map(BTreeMap::new()))
+            {
+                Variable::Map(_) => section = Some(name),
+                _ => bail!(bad(&format!("`{}` is already a key", name))),
+            }
+
+            continue;
+        }
+
+        let split = match line.find(['=', ':']) {
+            Some(split) => split,
+            None => bail!(bad("Expected `key = value`")),
+        };
+
+        let key = line[..split].trim().to_string();
+        if key.is_empty() {
+            bail!(bad("Expected `key = value`"))
+        }
+
+        let value = line[split + 1..].trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|q| value.strip_prefix(*q).and_then(|x| x.strip_suffix(*q)))
+            .unwrap_or(value);
+
+        let table = match &section {
+            Some(section) => match root.get_mut(section) {
+                Some(Variable::Map(table)) => table,
+                _ => unreachable!(),
+            },
+            None => &mut root,
+        };
+
+        table.insert(key, Variable::Str(value.to_string()));
+    }
+
+    Ok(Variable::Map(root))
+}
+
+fn parse(text: &str, format: &str, file: Option<&str>) -> anyhow::Result<Variable> {
+    match format {
+        "toml" => parse_toml(text, file),
+        "ini" => parse_ini(text, file),
+        any => bail!(Err::UnknownConfigFormat(any.to_string())),
+    }
+}
+
+fn stringify(var: Variable) -> anyhow::Result<String> {
+    let table = to_toml(&Variable::Map(as_map(var)?))?;
+    Ok(toml::to_string(&table)?)
+}
+
+/// Reads config files, in TOML or INI, and writes TOML.
+pub fn config() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        // parse(text format?), where format is "toml" (the default) or "ini".
+        "parse" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let text = as_str(args.next().unwrap())?;
+
+            let format = match args.next() {
+                Some(format) => as_str(format)?.to_lowercase(),
+                None => "toml".to_string(),
+            };
+
+            parse(&text, &format, None)
+        }
+        // read(path format?)
+        // Without a format, `.ini` and `.cfg` files are read as INI,
+        // and everything else as TOML.
+        "read" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let path = as_str(args.next().unwrap())?;
+
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to open {}. (Does it exist?)", path))?;
+
+            let format = match args.next() {
+                Some(format) => as_str(format)?.to_lowercase(),
+                None => match Path::new(&path).extension().and_then(|x| x.to_str()) {
+                    Some("ini") | Some("cfg") => "ini".to_string(),
+                    _ => "toml".to_string(),
+                },
+            };
+
+            parse(&text, &format, Some(&path))
+        }
+        // stringify(map), as TOML.
+        "stringify" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(Variable::Str(stringify(args.into_iter().next().unwrap())?))
+        }
+        // write(path map), as TOML.
+        "write" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let path = as_str(args.next().unwrap())?;
+            let text = stringify(args.next().unwrap())?;
+
+            std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path))?;
+
+            Ok(Variable::Void)
+        }
+    }
+
+    map
+}
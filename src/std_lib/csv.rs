@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Read;
+
+use csv::{Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+use anyhow::Context;
+
+use super::{as_array, as_str, expect_args};
+
+/// The optional `header` and `delimiter` arguments that
+/// every reading function takes after its source.
+struct Options {
+    header: bool,
+    delimiter: u8,
+}
+
+fn options(args: impl Iterator<Item = Variable>) -> anyhow::Result<Options> {
+    let mut options = Options {
+        header: false,
+        delimiter: b',',
+    };
+
+    for (idx, arg) in args.enumerate() {
+        match (idx, arg) {
+            (0, Variable::Bool(header)) => options.header = header,
+            (0, any) => bail!(Err::VarTypeMismatch(Variable::Bool(true), any)),
+            (_, any) => options.delimiter = delimiter(any)?,
+        }
+    }
+
+    Ok(options)
+}
+
+fn delimiter(var: Variable) -> anyhow::Result<u8> {
+    match as_str(var)?.as_str() {
+        delimiter if delimiter.len() == 1 => Ok(delimiter.as_bytes()[0]),
+        any => bail!(Err::NotAChar(any.to_string())),
+    }
+}
+
+fn reader<R: Read>(source: R, options: &Options) -> Reader<R> {
+    ReaderBuilder::new()
+        .has_headers(options.header)
+        .delimiter(options.delimiter)
+        // Rows without a header may as well be different lengths.
+        .flexible(!options.header)
+        .from_reader(source)
+}
+
+/// The header row, if there is meant to be one. Its names have to be
+/// different, or the later fields would replace the earlier ones.
+fn header<R: Read>(reader: &mut Reader<R>, options: &Options) -> anyhow::Result<Option<StringRecord>> {
+    if !options.header {
+        return Ok(None);
+    }
+
+    let header = reader.headers().map_err(bad_csv)?.clone();
+
+    let mut seen = BTreeSet::new();
+    for name in &header {
+        if !seen.insert(name) {
+            bail!(Err::BadCsv(format!("`{}` is in the header more than once", name)))
+        }
+    }
+
+    Ok(Some(header))
+}
+
+fn row(record: StringRecord, header: Option<&StringRecord>) -> Variable {
+    match header {
+        Some(header) => Variable::Map(
+            header
+                .iter()
+                .zip(record.iter())
+                .map(|(key, x)| (key.to_string(), Variable::Str(x.to_string())))
+                .collect(),
+        ),
+        None => Variable::Array(
+            record
+                .iter()
+                .map(|x| Variable::Str(x.to_string()))
+                .collect(),
+        ),
+    }
+}
+
+fn bad_csv(err: csv::Error) -> Err {
+    let message = err.to_string();
+    Err::BadCsv(message.trim_start_matches("CSV error: ").to_string())
+}
+
+fn writer<W: std::io::Write>(dest: W, delimiter: Option<u8>) -> Writer<W> {
+    WriterBuilder::new()
+        .delimiter(delimiter.unwrap_or(b','))
+        .flexible(true)
+        .from_writer(dest)
+}
+
+fn read_all<R: Read>(source: R, options: Options) -> anyhow::Result<Variable> {
+    let mut reader = reader(source, &options);
+    let header = header(&mut reader, &options)?;
+
+    let mut rows = vec![];
+    for record in reader.into_records() {
+        rows.push(row(record.map_err(bad_csv)?, header.as_ref()));
+    }
+
+    Ok(Variable::Array(rows))
+}
+
+/// Writes rows, which are either all arrays or all maps. For maps, the
+/// header is every key in any of them, so that none get left out.
+fn write_all<W: std::io::Write>(rows: Vec<Variable>, writer: &mut Writer<W>) -> anyhow::Result<()> {
+    let header: Option<BTreeSet<String>> = match rows.first() {
+        Some(Variable::Map(_)) => Some(
+            rows.iter()
+                .filter_map(|row| match row {
+                    Variable::Map(map) => Some(map.keys().cloned()),
+                    _ => None,
+                })
+                .flatten()
+                .collect(),
+        ),
+        _ => None,
+    };
+
+    if let Some(header) = &header {
+        writer.write_record(header).map_err(bad_csv)?;
+    }
+
+    let field = |var: &Variable| match var {
+        Variable::Void => String::new(),
+        any => any.to_string(),
+    };
+
+    for row in rows {
+        let fields: Vec<String> = match (&header, row) {
+            (None, Variable::Array(arr)) => arr.iter().map(field).collect(),
+            (Some(header), Variable::Map(map)) => header
+                .iter()
+                .map(|key| map.get(key).map(field).unwrap_or_default())
+                .collect(),
+            (None, any) => bail!(Err::VarTypeMismatch(Variable::Array(vec![]), any)),
+            (Some(_), any) => bail!(Err::VarTypeMismatch(Variable::Map(BTreeMap::new()), any)),
+        };
+
+        writer.write_record(&fields).map_err(bad_csv)?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn open(path: &str) -> anyhow::Result<File> {
+    File::open(path).with_context(|| format!("Failed to open {}. (Does it exist?)", path))
+}
+
+/// Reads and writes CSV, quoted as in RFC 4180.
+pub fn csv() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        // parse(text header? delimiter?)
+        // Each row is an array of fields, or if header is true,
+        // a map from the names in the first row.
+        "parse" => |args, _| {
+            expect_args(&args, 1, 3)?;
+            let mut args = args.into_iter();
+            let text = as_str(args.next().unwrap())?;
+
+            read_all(text.as_bytes(), options(args)?)
+        }
+        // read(path header? delimiter?), like parse but from a file.
+        "read" => |args, _| {
+            expect_args(&args, 1, 3)?;
+            let mut args = args.into_iter();
+            let file = open(&as_str(args.next().unwrap())?)?;
+
+            read_all(file, options(args)?)
+        }
+        // rows(path header? delimiter?)
+        // Like read, but reads one row at a time as it's looped over,
+        // so the whole file is never held at once.
+        "rows" => |args, _| {
+            expect_args(&args, 1, 3)?;
+            let mut args = args.into_iter();
+            let file = open(&as_str(args.next().unwrap())?)?;
+            let options = options(args)?;
+
+            let mut reader = reader(file, &options);
+            let header = header(&mut reader, &options)?;
+
+            let rows = reader.into_records().map(move |record| {
+                Ok(row(record.map_err(bad_csv)?, header.as_ref()))
+            });
+
+            Ok(Variable::Handle(Handle::iterator(rows)))
+        }
+        // stringify(rows delimiter?)
+        "stringify" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let rows = as_array(args.next().unwrap())?;
+
+            let mut out = writer(vec![], args.next().map(delimiter).transpose()?);
+
+            write_all(rows, &mut out)?;
+
+            let bytes = out.into_inner().map_err(|err| err.into_error())?;
+            Ok(Variable::Str(String::from_utf8(bytes)?))
+        }
+        // write(path rows delimiter?)
+        "write" => |args, _| {
+            expect_args(&args, 2, 3)?;
+            let mut args = args.into_iter();
+            let path = as_str(args.next().unwrap())?;
+            let rows = as_array(args.next().unwrap())?;
+
+            let file = File::create(&path)
+                .with_context(|| format!("Failed to create {}", path))?;
+
+            let mut out = writer(file, args.next().map(delimiter).transpose()?);
+
+            write_all(rows, &mut out)?;
+
+            Ok(Variable::Void)
+        }
+    }
+
+    map
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use md5::Md5;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+
+use super::{as_bytes, as_map, as_str, expect_args, from_bytes};
+
+/// Everything but the characters URLs leave alone (RFC 3986's "unreserved").
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Strings are taken as their UTF-8 bytes, and arrays as bytes.
+fn as_data(var: Variable) -> anyhow::Result<Vec<u8>> {
+    match var {
+        Variable::Str(string) | Variable::Html(string) => Ok(string.into_bytes()),
+        any => as_bytes(any),
+    }
+}
+
+fn url_safe(var: Option<Variable>) -> anyhow::Result<bool> {
+    match var {
+        Some(Variable::Bool(url_safe)) => Ok(url_safe),
+        Some(any) => bail!(Err::VarTypeMismatch(Variable::Bool(true), any)),
+        None => Ok(false),
+    }
+}
+
+fn url_encode(text: &str) -> String {
+    utf8_percent_encode(text, COMPONENT).to_string()
+}
+
+fn utf8(bytes: Vec<u8>) -> anyhow::Result<String> {
+    String::from_utf8(bytes).map_err(|err| Err::InvalidUtf8(err.utf8_error().valid_up_to()).into())
+}
+
+/// Decodes percent-encoded text. Fails if the bytes aren't valid UTF-8.
+pub(crate) fn url_decode(text: &str) -> anyhow::Result<String> {
+    utf8(percent_decode_str(text).collect())
+}
+
+/// A map from a query string, like `a=1&b=two+words`. `+` counts as a
+/// space. Shared with the server, for the query of each request.
+pub(crate) fn parse_query(query: &str) -> anyhow::Result<BTreeMap<String, Variable>> {
+    let mut out = BTreeMap::new();
+
+    for pair in query.split('&').filter(|x| !x.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        out.insert(
+            url_decode(&name.replace('+', " "))?,
+            Variable::Str(url_decode(&value.replace('+', " "))?),
+        );
+    }
+
+    Ok(out)
+}
+
+/// Converts data to and from text formats, and hashes it.
+pub fn encoding() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    // Hashes data, giving the digest in hex.
+    macro_rules! digest_fn {
+        ($($name: expr => $hasher: ty),*) => {
+            insert_fn! {
+                $($name => |args, _| {
+                    expect_args(&args, 1, 1)?;
+                    let data = as_data(args.into_iter().next().unwrap())?;
+
+                    Ok(Variable::Str(hex::encode(<$hasher>::digest(&data))))
+                })*
+            }
+        };
+    }
+
+    insert_fn! {
+        // base64_encode(data url_safe?)
+        // The URL-safe alphabet uses `-` and `_`, and leaves off the padding.
+        "base64_encode" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let data = as_data(args.next().unwrap())?;
+
+            Ok(Variable::Str(match url_safe(args.next())? {
+                true => base64::encode_config(data, base64::URL_SAFE_NO_PAD),
+                false => base64::encode(data),
+            }))
+        }
+        // base64_decode(text url_safe?), giving bytes. Padding is optional.
+        "base64_decode" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let text = as_str(args.next().unwrap())?;
+
+            let config = match url_safe(args.next())? {
+                true => base64::URL_SAFE_NO_PAD,
+                false => base64::STANDARD_NO_PAD,
+            };
+
+            match base64::decode_config(text.trim_end_matches('='), config) {
+                Ok(bytes) => Ok(from_bytes(&bytes)),
+                Err(err) => bail!(Err::BadEncoding("base64", err.to_string())),
+            }
+        }
+        "hex_encode" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(Variable::Str(hex::encode(as_data(args.into_iter().next().unwrap())?)))
+        }
+        // hex_decode(text), giving bytes.
+        "hex_decode" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let text = as_str(args.into_iter().next().unwrap())?;
+
+            match hex::decode(text) {
+                Ok(bytes) => Ok(from_bytes(&bytes)),
+                Err(err) => bail!(Err::BadEncoding("hex", err.to_string())),
+            }
+        }
+        // Percent-encodes text to go in a URL, e.g. as a path segment
+        // or query value. Only letters, digits and `-_.~` are left alone.
+        "url_encode" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(Variable::Str(url_encode(&as_str(args.into_iter().next().unwrap())?)))
+        }
+        "url_decode" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let text = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Str(url_decode(&text)?))
+        }
+        // query_encode(map), e.g. `a=1&b=two%20words`.
+        "query_encode" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let query = as_map(args.into_iter().next().unwrap())?;
+
+            let pairs: Vec<String> = query
+                .iter()
+                .map(|(name, value)| format!("{}={}", url_encode(name), url_encode(&value.to_string())))
+                .collect();
+
+            Ok(Variable::Str(pairs.join("&")))
+        }
+        // query_decode(text), a map from a query string. `+` counts as a space.
+        "query_decode" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let text = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Map(parse_query(text.trim_start_matches('?'))?))
+        }
+        // utf8(bytes), the text they encode. Fails if they aren't valid UTF-8.
+        "utf8" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(Variable::Str(utf8(as_bytes(args.into_iter().next().unwrap())?)?))
+        }
+        "is_utf8" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let bytes = as_bytes(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Bool(std::str::from_utf8(&bytes).is_ok()))
+        }
+        // crc32(data), as a number.
+        "crc32" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let data = as_data(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Num(crc32fast::hash(&data) as f64))
+        }
+    }
+
+    digest_fn!(
+        "md5" => Md5,
+        "sha1" => Sha1,
+        "sha256" => Sha256
+    );
+
+    map
+}
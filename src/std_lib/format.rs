@@ -0,0 +1,235 @@
+//! The `format` native, which fills `{}` placeholders in a string.
+
+use crate::data_types::Variable;
+use crate::errors::Err;
+use crate::interpreter;
+use crate::scope::Scope;
+use crate::{lexer, parser};
+
+use anyhow::bail;
+use anyhow::Context;
+
+use super::as_str;
+
+/// The largest width or precision, the same as Rust's.
+const MAX_WIDTH: usize = u16::MAX as usize;
+
+/// Everything after the `:` in a placeholder, e.g. `*^10.2`.
+struct Spec {
+    fill: char,
+    /// `<`, `^` or `>`. Numbers go right by default, everything else left.
+    align: Option<char>,
+    /// Pad numbers with zeros, after their sign.
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> anyhow::Result<Self> {
+        let chars: Vec<char> = spec.chars().collect();
+        let mut pos = 0;
+
+        let mut out = Spec {
+            fill: ' ',
+            align: None,
+            zero: false,
+            width: 0,
+            precision: None,
+        };
+
+        let is_align = |c: Option<&char>| matches!(c, Some('<' | '^' | '>'));
+
+        if is_align(chars.get(1)) {
+            out.fill = chars[0];
+            out.align = Some(chars[1]);
+            pos = 2;
+        } else if is_align(chars.first()) {
+            out.align = Some(chars[0]);
+            pos = 1;
+        }
+
+        if chars.get(pos) == Some(&'0') {
+            out.zero = true;
+            pos += 1;
+        }
+
+        // Checked here, so that `apply` can't be asked for
+        // more padding or digits than it can allocate.
+        let number = |pos: &mut usize| {
+            let start = *pos;
+            while chars.get(*pos).is_some_and(char::is_ascii_digit) {
+                *pos += 1;
+            }
+            if start == *pos {
+                return Ok(None);
+            }
+
+            let digits: String = chars[start..*pos].iter().collect();
+            match digits.parse::<usize>() {
+                Ok(num) if num <= MAX_WIDTH => Ok(Some(num)),
+                _ => bail!(Err::BadFormat(format!(
+                    "`{}` is too large in `{}` (the most is {})",
+                    digits, spec, MAX_WIDTH
+                ))),
+            }
+        };
+
+        out.width = number(&mut pos)?.unwrap_or(0);
+
+        if chars.get(pos) == Some(&'.') {
+            pos += 1;
+            out.precision = match number(&mut pos)? {
+                Some(precision) => Some(precision),
+                None => bail!(Err::BadFormat(format!("missing precision in `{}`", spec))),
+            };
+        }
+
+        if pos != chars.len() {
+            bail!(Err::BadFormat(format!("unknown format spec `{}`", spec)))
+        }
+
+        Ok(out)
+    }
+
+    fn apply(&self, var: &Variable) -> String {
+        let text = match (var, self.precision) {
+            (Variable::Num(num), Some(precision)) => format!("{:.*}", precision, num),
+            // Like Rust, precision cuts strings short.
+            (Variable::Str(string), Some(precision)) => string.chars().take(precision).collect(),
+            (any, _) => any.to_string(),
+        };
+
+        let len = text.chars().count();
+        if len >= self.width {
+            return text;
+        }
+        let padding = self.width - len;
+
+        if self.zero && matches!(var, Variable::Num(_)) {
+            return match text.strip_prefix('-') {
+                Some(digits) => format!("-{}{}", "0".repeat(padding), digits),
+                None => format!("{}{}", "0".repeat(padding), text),
+            };
+        }
+
+        let fill = |count: usize| self.fill.to_string().repeat(count);
+
+        let align = match (self.align, var) {
+            (Some(align), _) => align,
+            (None, Variable::Num(_)) => '>',
+            (None, _) => '<',
+        };
+
+        match align {
+            '>' => fill(padding) + &text,
+            '^' => fill(padding / 2) + &text + &fill(padding - padding / 2),
+            _ => text + &fill(padding),
+        }
+    }
+}
+
+/// Runs a placeholder like `{user.name}` or `{add(x 1)}` in the caller's scope.
+fn evaluate(expr: &str, scope: &mut Scope) -> anyhow::Result<Variable> {
+    let tokens = lexer::make_tokens(expr.as_bytes())?;
+    let mut nodes = parser::make_fn_call(parser::parse(tokens)?)?;
+
+    if nodes.len() != 1 {
+        bail!(Err::BadFormat(format!("`{}` is not a single value", expr)))
+    }
+
+    Ok(interpreter::into_var(vec![nodes.remove(0)], scope)?.remove(0))
+}
+
+/// Splits a placeholder at the first `:` that isn't inside a string.
+fn split_spec(placeholder: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+
+    for (idx, c) in placeholder.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (':', None) => return (&placeholder[..idx], Some(&placeholder[idx + 1..])),
+            _ => {}
+        }
+    }
+
+    (placeholder, None)
+}
+
+/// format(template args...)
+///
+/// - `{}` is the next argument, `{0}` is the first one
+/// - anything else, like `{name}` or `{len(items)}`, is evaluated in scope
+/// - `{{` and `}}` are literal braces
+/// - `{:fill align width .precision}` works like Rust's, e.g. `{price:>8.2}`
+pub(crate) fn format(args: Vec<Variable>, scope: &mut Scope) -> anyhow::Result<Variable> {
+    if args.is_empty() {
+        bail!(Err::MissingArgs("format".to_string()))
+    }
+
+    let mut args = args.into_iter();
+    let template = as_str(args.next().unwrap())?;
+    let args: Vec<Variable> = args.collect();
+
+    let mut out = String::new();
+    let mut next_arg = 0;
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '}' => bail!(Err::BadFormat("unmatched `}` (use `}}` for a literal one)".to_string())),
+            '{' => {
+                let mut placeholder = String::new();
+                let mut quote = None;
+
+                loop {
+                    match (chars.next(), quote) {
+                        (Some('}'), None) => break,
+                        (Some(c @ ('"' | '\'')), None) => {
+                            quote = Some(c);
+                            placeholder.push(c);
+                        }
+                        (Some(c), Some(q)) if c == q => {
+                            quote = None;
+                            placeholder.push(c);
+                        }
+                        (Some(c), _) => placeholder.push(c),
+                        (None, _) => bail!(Err::BadFormat(
+                            "unclosed `{` (use `{{` for a literal one)".to_string()
+                        )),
+                    }
+                }
+
+                let (expr, spec) = split_spec(&placeholder);
+                let expr = expr.trim();
+
+                let var = if expr.is_empty() {
+                    next_arg += 1;
+                    args.get(next_arg - 1).cloned().ok_or(Err::MissingArgs("format".to_string()))?
+                } else if let Ok(idx) = expr.parse::<usize>() {
+                    args.get(idx).cloned().ok_or(Err::OutOfBoundsIndex(args.len(), idx))?
+                } else {
+                    evaluate(expr, scope)
+                        .with_context(|| format!("Error originated in the placeholder `{{{}}}`", expr))?
+                };
+
+                out += &match spec {
+                    Some(spec) => Spec::parse(spec)?.apply(&var),
+                    None => var.to_string(),
+                };
+            }
+            c => out.push(c),
+        }
+    }
+
+    Ok(Variable::Str(out))
+}
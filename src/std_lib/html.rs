@@ -0,0 +1,617 @@
+use std::collections::BTreeMap;
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag};
+
+use super::{as_int, as_str, expect_args};
+
+/// Elements that can't have content, and so have no closing tag.
+const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is code rather than text, which
+/// entities would break.
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// Makes text safe to put in HTML, both as content and in attributes.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            '\'' => out += "&#39;",
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Renders the content of an element: HTML as is, arrays one
+/// element after another, and everything else escaped.
+fn content(var: &Variable, out: &mut String) {
+    match var {
+        Variable::Html(html) => *out += html,
+        Variable::Array(arr) => arr.iter().for_each(|x| content(x, out)),
+        Variable::Void => {}
+        any => *out += &escape(&any.to_string()),
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+}
+
+/// Renders attributes, given as a map, or as pairs of names and values.
+/// `true` gives an attribute with no value, `false` and void leave it out.
+fn attributes(args: Vec<Variable>) -> anyhow::Result<String> {
+    let pairs: Vec<(String, Variable)> = match args.len() {
+        0 => return Ok(String::new()),
+        1 => match args.into_iter().next().unwrap() {
+            Variable::Map(map) => map.into_iter().collect(),
+            // The old way: one pre-quoted string, which is put in as it is.
+            // It can't be escaped, so it has to be marked with `html.raw`.
+            Variable::Str(string) | Variable::Html(string) if string.is_empty() => return Ok(string),
+            Variable::Html(string) => return Ok(format!(" {}", string)),
+            Variable::Str(_) => bail!(Err::BadHtml(
+                "a string of attributes has to be wrapped in `html.raw`".to_string()
+            )),
+            any => bail!(Err::VarTypeMismatch(Variable::Map(BTreeMap::new()), any)),
+        },
+        len if len % 2 == 0 => {
+            let mut args = args.into_iter();
+            let mut pairs = vec![];
+            while let (Some(name), Some(value)) = (args.next(), args.next()) {
+                match name {
+                    Variable::Str(name) => pairs.push((name, value)),
+                    any => bail!(Err::VarTypeMismatch(Variable::Str("".to_string()), any)),
+                }
+            }
+            pairs
+        }
+        _ => bail!(Err::BadHtml(
+            "expected a map, or pairs of names and values".to_string()
+        )),
+    };
+
+    let mut out = String::new();
+
+    for (name, value) in pairs {
+        if !is_name(&name) {
+            bail!(Err::BadHtml(format!("`{}` is not a valid name", name)))
+        }
+
+        match value {
+            Variable::Bool(false) | Variable::Void => {}
+            Variable::Bool(true) => out += &format!(" {}", name),
+            value => out += &format!(" {}=\"{}\"", name, escape(&value.to_string())),
+        }
+    }
+
+    Ok(out)
+}
+
+/// tag(content attributes...), or tag(attributes...) for void elements.
+fn element(tag: &str, args: Vec<Variable>) -> anyhow::Result<Variable> {
+    let mut args = args.into_iter();
+
+    if VOID_ELEMENTS.contains(&tag) {
+        let mut args: Vec<Variable> = args.collect();
+        // Void elements used to take an (ignored) body like the rest.
+        if args.first() == Some(&Variable::Str(String::new())) {
+            args.remove(0);
+        }
+
+        return Ok(Variable::Html(format!("<{}{}>", tag, attributes(args)?)));
+    }
+
+    let body = match args.next() {
+        Some(body) => body,
+        None => bail!(Err::MissingArgs(format!("html.{}", tag))),
+    };
+
+    let mut out = format!("<{}{}>", tag, attributes(args.collect())?);
+
+    match body {
+        // Can't be escaped, but mustn't be able to close the element early.
+        Variable::Str(code) if RAW_TEXT_ELEMENTS.contains(&tag) => {
+            out += &code.replace("</", "<\\/")
+        }
+        body => content(&body, &mut out),
+    }
+
+    out += &format!("</{}>", tag);
+
+    Ok(Variable::Html(out))
+}
+
+/// The head or body of a document, wrapped in its element
+/// unless it already was.
+fn section(tag: &str, var: Variable) -> anyhow::Result<Variable> {
+    let wrapped = match &var {
+        // `<head>` or `<head class="...">`, but not `<header>`.
+        Variable::Html(html) => html
+            .strip_prefix(&format!("<{}", tag))
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(|c| c == '>' || c.is_ascii_whitespace()),
+        _ => false,
+    };
+
+    if wrapped {
+        Ok(var)
+    } else {
+        element(tag, vec![var])
+    }
+}
+
+/// Elements that are part of a line of text, and so are
+/// kept on one line when pretty-printing.
+const INLINE_ELEMENTS: [&str; 30] = [
+    "a", "abbr", "b", "bdi", "bdo", "br", "cite", "code", "data", "del", "dfn", "em", "i", "img",
+    "ins", "kbd", "label", "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup",
+    "time", "u", "var", "wbr",
+];
+
+/// Elements whose content is printed exactly as it was.
+const PREFORMATTED_ELEMENTS: [&str; 4] = ["script", "style", "pre", "textarea"];
+
+enum Markup {
+    Text(String),
+    /// A comment or doctype.
+    Other(String),
+    Element {
+        name: String,
+        open: String,
+        children: Vec<Markup>,
+        close: String,
+    },
+}
+
+impl Markup {
+    fn is_inline(&self) -> bool {
+        match self {
+            Markup::Text(_) => true,
+            Markup::Other(_) => false,
+            Markup::Element { name, children, .. } => {
+                INLINE_ELEMENTS.contains(&name.as_str()) && children.iter().all(Markup::is_inline)
+            }
+        }
+    }
+
+    fn compact(&self, out: &mut String) {
+        match self {
+            Markup::Text(text) | Markup::Other(text) => *out += text,
+            Markup::Element {
+                open,
+                children,
+                close,
+                ..
+            } => {
+                *out += open;
+                children.iter().for_each(|x| x.compact(out));
+                *out += close;
+            }
+        }
+    }
+
+    fn pretty(&self, indent: &str, depth: usize, out: &mut String) {
+        let prefix = indent.repeat(depth);
+
+        match self {
+            Markup::Text(text) => {
+                let text = text.trim();
+                if !text.is_empty() {
+                    *out += &format!("{}{}\n", prefix, text);
+                }
+            }
+            Markup::Other(other) => *out += &format!("{}{}\n", prefix, other),
+            Markup::Element {
+                name,
+                open,
+                children,
+                close,
+            } => {
+                if PREFORMATTED_ELEMENTS.contains(&name.as_str())
+                    || children.iter().all(Markup::is_inline)
+                {
+                    *out += &prefix;
+                    self.compact(out);
+                    *out += "\n";
+                } else {
+                    *out += &format!("{}{}\n", prefix, open);
+                    children
+                        .iter()
+                        .for_each(|x| x.pretty(indent, depth + 1, out));
+                    if !close.is_empty() {
+                        *out += &format!("{}{}\n", prefix, close);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Splits HTML into a tree. It's forgiving, since it's only used
+/// for laying the markup out: anything it doesn't understand is kept as text.
+fn parse(html: &str) -> Vec<Markup> {
+    // The elements that are still open, each with its children so far.
+    let mut stack: Vec<(String, String, Vec<Markup>)> = vec![];
+    let mut done: Vec<Markup> = vec![];
+    let mut rest = html;
+
+    fn push(
+        stack: &mut [(String, String, Vec<Markup>)],
+        done: &mut Vec<Markup>,
+        markup: Markup,
+    ) {
+        match stack.last_mut() {
+            Some((_, _, children)) => children.push(markup),
+            None => done.push(markup),
+        }
+    }
+
+    while !rest.is_empty() {
+        let end = match rest.find('<') {
+            Some(0) => tag_end(rest),
+            Some(i) => {
+                push(&mut stack, &mut done, Markup::Text(rest[..i].to_string()));
+                rest = &rest[i..];
+                continue;
+            }
+            None => None,
+        };
+
+        let end = match end {
+            Some(end) => end,
+            None => {
+                push(&mut stack, &mut done, Markup::Text(rest.to_string()));
+                break;
+            }
+        };
+
+        let tag = &rest[..end];
+        rest = &rest[end..];
+
+        if tag.starts_with("<!") {
+            push(&mut stack, &mut done, Markup::Other(tag.to_string()));
+        } else if let Some(name) = tag.strip_prefix("</") {
+            let name = name.trim_end_matches('>').trim().to_ascii_lowercase();
+
+            // Closes everything opened since, in case they weren't.
+            if let Some(pos) = stack.iter().rposition(|(x, _, _)| *x == name) {
+                while stack.len() > pos {
+                    let (name, open, children) = stack.pop().unwrap();
+                    let close = if stack.len() == pos {
+                        tag.to_string()
+                    } else {
+                        String::new()
+                    };
+                    push(
+                        &mut stack,
+                        &mut done,
+                        Markup::Element {
+                            name,
+                            open,
+                            children,
+                            close,
+                        },
+                    );
+                }
+            } else {
+                push(&mut stack, &mut done, Markup::Text(tag.to_string()));
+            }
+        } else {
+            let name: String = tag[1..]
+                .chars()
+                .take_while(|c| !c.is_whitespace() && !matches!(c, '>' | '/'))
+                .collect::<String>()
+                .to_ascii_lowercase();
+
+            if VOID_ELEMENTS.contains(&name.as_str()) || tag.ends_with("/>") {
+                let element = Markup::Element {
+                    name,
+                    open: tag.to_string(),
+                    children: vec![],
+                    close: String::new(),
+                };
+                push(&mut stack, &mut done, element);
+            } else if PREFORMATTED_ELEMENTS.contains(&name.as_str()) {
+                // Content is taken as it is, right up to the closing tag.
+                let closing = format!("</{}", name);
+                // ASCII only, so that indices into the lowercase copy
+                // are still indices into `rest`.
+                let (inner, close) = match rest.to_ascii_lowercase().find(&closing) {
+                    Some(i) => {
+                        let close_end =
+                            rest[i..].find('>').map(|x| i + x + 1).unwrap_or(rest.len());
+                        let parts = (&rest[..i], &rest[i..close_end]);
+                        rest = &rest[close_end..];
+                        parts
+                    }
+                    None => {
+                        let parts = (rest, "");
+                        rest = "";
+                        parts
+                    }
+                };
+
+                let children = if inner.is_empty() {
+                    vec![]
+                } else {
+                    vec![Markup::Text(inner.to_string())]
+                };
+                let element = Markup::Element {
+                    name,
+                    open: tag.to_string(),
+                    children,
+                    close: close.to_string(),
+                };
+                push(&mut stack, &mut done, element);
+            } else {
+                stack.push((name, tag.to_string(), vec![]));
+            }
+        }
+    }
+
+    // Anything left open stays that way.
+    while let Some((name, open, children)) = stack.pop() {
+        push(
+            &mut stack,
+            &mut done,
+            Markup::Element {
+                name,
+                open,
+                children,
+                close: String::new(),
+            },
+        );
+    }
+
+    done
+}
+
+/// Where the tag (or comment) at the start of `html` ends, skipping
+/// over any `>` in quoted attribute values.
+fn tag_end(html: &str) -> Option<usize> {
+    if html.starts_with("<!--") {
+        return html.find("-->").map(|x| x + 3);
+    }
+
+    let mut quote = None;
+
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Lays HTML out with one element per line, indenting their children.
+/// Elements holding only text and inline elements stay on one line.
+fn pretty(html: &str, indent: &str) -> String {
+    let mut out = String::new();
+
+    for markup in parse(html) {
+        markup.pretty(indent, 0, &mut out);
+    }
+
+    out
+}
+
+/// Whether a link would run code, or show something other than
+/// what it points to, when clicked. Browsers ignore whitespace and
+/// case in the scheme, so this does too.
+fn is_unsafe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .take(11)
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    ["javascript:", "vbscript:", "data:"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
+fn safe_url(url: CowStr) -> CowStr {
+    if is_unsafe_url(&url) {
+        CowStr::Borrowed("")
+    } else {
+        url
+    }
+}
+
+fn markdown_to_html(markdown: &str, allow_html: bool) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(html) if !allow_html => Event::Text(html),
+        Event::Start(Tag::Link(kind, url, title)) => {
+            Event::Start(Tag::Link(kind, safe_url(url), title))
+        }
+        Event::Start(Tag::Image(kind, url, title)) => {
+            Event::Start(Tag::Image(kind, safe_url(url), title))
+        }
+        event => event,
+    });
+
+    let mut out = String::new();
+    pulldown_cmark::html::push_html(&mut out, events);
+
+    out
+}
+
+/// Joins values for `con`. If any of them are HTML, the rest are
+/// escaped and the result is HTML too. Otherwise, it's a plain string.
+pub(crate) fn concat(args: Vec<Variable>) -> Variable {
+    if args.iter().any(|x| matches!(x, Variable::Html(_))) {
+        let mut out = String::new();
+        for arg in args {
+            match arg {
+                Variable::Html(html) => out += &html,
+                any => out += &escape(&any.to_string()),
+            }
+        }
+        Variable::Html(out)
+    } else {
+        Variable::Str(args.iter().map(|x| x.to_string()).collect())
+    }
+}
+
+/// Builds HTML. Text is escaped unless it's wrapped with `raw`.
+pub fn html() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_elem {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    macro_rules! elem {
+        ($($tag: expr),*) => {
+            insert_elem! {
+                $($tag => |args, _| element($tag, args))*
+            }
+        };
+    }
+
+    insert_elem! {
+        // custom(name content attributes...), for any other element.
+        "custom" => |args, _| {
+            if args.len() < 2 {
+                bail!(
+                    Err::MissingArgs("html.custom".to_string())
+                )
+            }
+
+            let mut args = args.into_iter();
+
+            let name = match args.next().unwrap() {
+                Variable::Str(name) if is_name(&name) => name,
+                any => bail!(Err::BadHtml(format!("`{}` is not a valid element name", any))),
+            };
+
+            element(&name, args.collect())
+        }
+        // For HTML comment
+        "comment" => |args, _| {
+            let mut comment_content = String::new();
+
+            for arg in args {
+                comment_content += &arg.to_string();
+            }
+
+            // Otherwise, the comment could be ended early.
+            Ok(Variable::Html(format!("<!-- {} -->", comment_content.replace("-->", "--&gt;"))))
+        }
+        // markdown_to_html(markdown allow_html?)
+        // HTML written in the Markdown is escaped like any other text,
+        // unless allow_html is true. Links and images that would run
+        // code lose their URL either way.
+        "markdown_to_html" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let markdown = as_str(args.next().unwrap())?;
+
+            let allow_html = match args.next() {
+                Some(Variable::Bool(allow_html)) => allow_html,
+                Some(any) => bail!(Err::VarTypeMismatch(Variable::Bool(true), any)),
+                None => false,
+            };
+
+            Ok(Variable::Html(markdown_to_html(&markdown, allow_html)))
+        }
+        // Trusts a string to already be safe HTML, so it isn't escaped.
+        "raw" => |args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(1, args.len()))
+            }
+
+            Ok(Variable::Html(args[0].to_string()))
+        }
+        // document(head body attributes...), a whole page. The attributes
+        // are for the <html> element, e.g. "lang" "en".
+        "document" => |args, _| {
+            if args.len() < 2 {
+                bail!(
+                    Err::MissingArgs("html.document".to_string())
+                )
+            }
+
+            let mut args = args.into_iter();
+
+            let head = section("head", args.next().unwrap())?;
+            let body = section("body", args.next().unwrap())?;
+
+            let mut out = format!("<!DOCTYPE html><html{}>", attributes(args.collect())?);
+            content(&head, &mut out);
+            content(&body, &mut out);
+            out += "</html>";
+
+            Ok(Variable::Html(out))
+        }
+        // pretty(html indent?), where indent is a number of spaces
+        // or the string to indent with. Tabs by default.
+        "pretty" => |args, _| {
+            expect_args(&args, 1, 2)?;
+
+            let mut args = args.into_iter();
+            let html = as_str(args.next().unwrap())?;
+
+            let indent = match args.next() {
+                None => "\t".to_string(),
+                Some(Variable::Str(indent)) => indent,
+                Some(any) => " ".repeat(as_int(any)?.max(0) as usize),
+            };
+
+            Ok(Variable::Html(pretty(&html, &indent)))
+        }
+        "escape" => |args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(1, args.len()))
+            }
+
+            Ok(Variable::Str(escape(&args[0].to_string())))
+        }
+    }
+
+    // Every HTML5 element. `tag` isn't one, but it's been here too long to drop.
+    elem!(
+        "a", "abbr", "address", "area", "article", "aside", "audio", "b", "base", "bdi", "bdo",
+        "blockquote", "body", "br", "button", "canvas", "caption", "cite", "code", "col",
+        "colgroup", "data", "datalist", "dd", "del", "details", "dfn", "dialog", "div", "dl", "dt",
+        "em", "embed", "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3",
+        "h4", "h5", "h6", "head", "header", "hgroup", "hr", "html", "i", "iframe", "img", "input",
+        "ins", "kbd", "label", "legend", "li", "link", "main", "map", "mark", "menu", "meta",
+        "meter", "nav", "noscript", "object", "ol", "optgroup", "option", "output", "p",
+        "picture", "pre", "progress", "q", "rp", "rt", "ruby", "s", "samp", "script", "search",
+        "section", "select", "slot", "small", "source", "span", "strong", "style", "sub",
+        "summary", "sup", "table", "tbody", "td", "template", "textarea", "tfoot", "th", "thead",
+        "time", "title", "tr", "track", "u", "ul", "var", "video", "wbr", "tag"
+    );
+
+    map
+}
@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use once_cell::sync::Lazy;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::Method;
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+use anyhow::Context;
+
+use super::{as_array, as_bytes, as_duration, as_map, as_str, expect_args, from_bytes};
+
+/// Shared by every request, so that connections get reused. Never
+/// dropped, which would panic inside an async runtime too.
+static CLIENT: Lazy<Client> = Lazy::new(Client::new);
+
+/// A fresh request, as returned by `internet.request`.
+fn request(method: String, url: String) -> BTreeMap<String, Variable> {
+    let mut req = BTreeMap::new();
+    req.insert("method".to_string(), Variable::Str(method));
+    req.insert("url".to_string(), Variable::Str(url));
+    req.insert("headers".to_string(), Variable::Map(BTreeMap::new()));
+    req.insert("query".to_string(), Variable::Map(BTreeMap::new()));
+    req
+}
+
+/// Sets `req[field][key]`, for builder functions like `header`.
+fn set_in(args: Vec<Variable>, field: &str) -> anyhow::Result<Variable> {
+    expect_args(&args, 3, 3)?;
+    let mut args = args.into_iter();
+    let mut req = as_map(args.next().unwrap())?;
+    let key = as_str(args.next().unwrap())?;
+    let value = args.next().unwrap();
+
+    let mut inner = match req.remove(field) {
+        Some(inner) => as_map(inner)?,
+        None => BTreeMap::new(),
+    };
+    inner.insert(key, value);
+    req.insert(field.to_string(), Variable::Map(inner));
+
+    Ok(Variable::Map(req))
+}
+
+/// Sets `req[field]`, for builder functions like `timeout`.
+fn set(args: Vec<Variable>, field: &str) -> anyhow::Result<Variable> {
+    expect_args(&args, 2, 2)?;
+    let mut args = args.into_iter();
+    let mut req = as_map(args.next().unwrap())?;
+
+    req.insert(field.to_string(), args.next().unwrap());
+
+    Ok(Variable::Map(req))
+}
+
+/// Turns a request map into something reqwest can send.
+fn build(mut req: BTreeMap<String, Variable>) -> anyhow::Result<(String, RequestBuilder)> {
+    let method = match req.remove("method") {
+        Some(method) => as_str(method)?.to_uppercase(),
+        None => "GET".to_string(),
+    };
+    let url = match req.remove("url") {
+        Some(url) => as_str(url)?,
+        None => bail!(Err::MissingArgs("url".to_string())),
+    };
+
+    let method = Method::from_bytes(method.as_bytes())
+        .with_context(|| format!("`{}` is not an HTTP method", method))?;
+
+    let mut builder = CLIENT.request(method, &url);
+
+    if let Some(headers) = req.remove("headers") {
+        for (name, value) in as_map(headers)? {
+            builder = builder.header(name, value.to_string());
+        }
+    }
+
+    if let Some(query) = req.remove("query") {
+        let query: Vec<(String, String)> = as_map(query)?
+            .into_iter()
+            .map(|(name, value)| (name, value.to_string()))
+            .collect();
+        builder = builder.query(&query);
+    }
+
+    match req.remove("body") {
+        Some(Variable::Str(body)) => builder = builder.body(body),
+        Some(Variable::Array(bytes)) => builder = builder.body(as_bytes(Variable::Array(bytes))?),
+        Some(Variable::Void) | None => {}
+        Some(any) => bail!(Err::VarTypeMismatch(Variable::Str("".to_string()), any)),
+    }
+
+    if let Some(timeout) = req.remove("timeout") {
+        builder = builder.timeout(as_duration(timeout)?);
+    }
+
+    if let Some(auth) = req.remove("basic_auth") {
+        let mut auth = as_array(auth)?.into_iter();
+        let user = match auth.next() {
+            Some(user) => as_str(user)?,
+            None => bail!(Err::MissingArgs("basic_auth".to_string())),
+        };
+        builder = builder.basic_auth(user, auth.next().map(as_str).transpose()?);
+    }
+
+    if let Some(token) = req.remove("bearer_auth") {
+        builder = builder.bearer_auth(as_str(token)?);
+    }
+
+    Ok((url, builder))
+}
+
+/// Sends a request, and reads the whole response. Error statuses
+/// (404, 500...) are not errors, only failing to get a response is.
+///
+/// reqwest's blocking client panics on a thread that is running an
+/// async runtime, which an embedder's thread may well be, so there
+/// the request is made from a thread of its own.
+fn send(req: BTreeMap<String, Variable>) -> anyhow::Result<Variable> {
+    if tokio::runtime::Handle::try_current().is_err() {
+        return send_here(req);
+    }
+
+    std::thread::scope(|scope| match scope.spawn(|| send_here(req)).join() {
+        Ok(res) => res,
+        Err(panic) => std::panic::resume_unwind(panic),
+    })
+}
+
+fn send_here(mut req: BTreeMap<String, Variable>) -> anyhow::Result<Variable> {
+    // An array is far bigger than the text it came from,
+    // so it's only made when asked for.
+    let bytes = match req.remove("bytes") {
+        Some(Variable::Bool(bytes)) => bytes,
+        Some(Variable::Void) | None => false,
+        Some(any) => bail!(Err::VarTypeMismatch(Variable::Bool(true), any)),
+    };
+    let (url, builder) = build(req)?;
+
+    let res = builder
+        .send()
+        .with_context(|| format!("Request to `{}` failed", url))?;
+
+    let status = res.status().as_u16();
+
+    // Repeated headers are joined with commas, as HTTP allows.
+    let mut headers: BTreeMap<String, Variable> = BTreeMap::new();
+    for (name, value) in res.headers() {
+        let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+        let value = match headers.remove(name.as_str()) {
+            Some(Variable::Str(prev)) => format!("{}, {}", prev, value),
+            _ => value,
+        };
+        headers.insert(name.to_string(), Variable::Str(value));
+    }
+
+    let body = res
+        .bytes()
+        .with_context(|| format!("Failed to read the response from `{}`", url))?;
+
+    let mut out = BTreeMap::new();
+    out.insert("status".to_string(), Variable::Num(status as f64));
+    out.insert("headers".to_string(), Variable::Map(headers));
+    out.insert("body".to_string(), Variable::Str(String::from_utf8_lossy(&body).into_owned()));
+    if bytes {
+        out.insert("bytes".to_string(), from_bytes(&body));
+    }
+
+    Ok(Variable::Map(out))
+}
+
+/// Just the body of a response, for `get` and `post`.
+fn body(res: Variable) -> Variable {
+    match res {
+        Variable::Map(mut res) => res.remove("body").unwrap_or(Variable::Void),
+        any => any,
+    }
+}
+
+/// Provides an HTTP client.
+pub fn internet() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+    insert_fn! {
+        // get(url) -> the body of the response.
+        "get" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let url = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(body(send(request("GET".to_string(), url))?))
+        }
+        // post(url body) -> the body of the response.
+        "post" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let url = as_str(args.next().unwrap())?;
+            let content = args.next().unwrap();
+
+            let mut req = request("POST".to_string(), url);
+            req.insert("body".to_string(), content);
+
+            Ok(body(send(req)?))
+        }
+        // request(method url) -> a request, to be filled in by
+        // the functions below and sent with `send`.
+        "request" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let method = as_str(args.next().unwrap())?;
+            let url = as_str(args.next().unwrap())?;
+
+            Ok(Variable::Map(request(method, url)))
+        }
+        "header" => |args, _| set_in(args, "headers")
+        "query" => |args, _| set_in(args, "query")
+        // body(req content) -> content is a string or an array of bytes.
+        "body" => |args, _| set(args, "body")
+        // timeout(req ms)
+        "timeout" => |args, _| set(args, "timeout")
+        "bearer_auth" => |args, _| set(args, "bearer_auth")
+        // bytes(req true) -> the response gets `bytes` too.
+        "bytes" => |args, _| set(args, "bytes")
+        // basic_auth(req user password?)
+        "basic_auth" => |args, _| {
+            expect_args(&args, 2, 3)?;
+            let mut args = args.into_iter();
+            let req = args.next().unwrap();
+
+            set(vec![req, Variable::Array(args.collect())], "basic_auth")
+        }
+        // send(req) -> { status, headers, body, bytes? }
+        "send" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            send(as_map(args.into_iter().next().unwrap())?)
+        }
+    };
+
+    map
+}
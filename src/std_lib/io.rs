@@ -0,0 +1,438 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+use anyhow::Context;
+
+use super::{as_bytes, as_str, expect_args, from_bytes};
+
+/// Opens `path` for writing according to a `write` mode.
+fn open_for_write(path: &str, mode: Option<Variable>) -> anyhow::Result<fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+
+    match mode.map(as_str).transpose()?.as_deref() {
+        // Default: create the file if needed, replace what was in it.
+        None | Some("truncate") => options.create(true).truncate(true),
+        Some("append") => options.create(true).append(true),
+        // Fails if the file already exists.
+        Some("create_new") => options.create_new(true),
+        // Fails if the file does not exist yet.
+        Some("overwrite") => options.truncate(true),
+        Some(any) => bail!(Err::UnknownWriteMode(any.to_string())),
+    };
+
+    options
+        .open(path)
+        .with_context(|| format!("Failed to open `{}` for writing", path))
+}
+
+fn write_to(path: &str, mode: Option<Variable>, content: &[u8]) -> anyhow::Result<Variable> {
+    open_for_write(path, mode)?
+        .write_all(content)
+        .with_context(|| format!("Failed to write to `{}`", path))?;
+
+    Ok(Variable::Void)
+}
+
+/// A file opened with `io.open`. Reading and writing go through
+/// separate buffers over the same file, so switching from one to
+/// the other syncs the file position first.
+pub(crate) struct OpenFile {
+    reader: Option<BufReader<fs::File>>,
+    writer: Option<BufWriter<fs::File>>,
+    closed: bool,
+}
+
+impl OpenFile {
+    fn reader(&mut self) -> anyhow::Result<&mut BufReader<fs::File>> {
+        if self.closed {
+            bail!(Err::ClosedHandle("file"))
+        }
+        // Anything written so far should be visible to reads.
+        if let Some(writer) = &mut self.writer {
+            writer.flush()?;
+        }
+        match &mut self.reader {
+            Some(reader) => Ok(reader),
+            None => bail!(Err::WrongFileMode("reading")),
+        }
+    }
+
+    fn writer(&mut self) -> anyhow::Result<&mut BufWriter<fs::File>> {
+        if self.closed {
+            bail!(Err::ClosedHandle("file"))
+        }
+        // The reader may have read ahead of what was handed out. Seeking
+        // drops that, so writes land right after the last thing read.
+        if let Some(reader) = &mut self.reader {
+            let pos = reader.stream_position()?;
+            reader.seek(SeekFrom::Start(pos))?;
+        }
+        match &mut self.writer {
+            Some(writer) => Ok(writer),
+            None => bail!(Err::WrongFileMode("writing")),
+        }
+    }
+}
+
+/// Reads one line from a file handle, without its line ending.
+/// Returns `None` at the end of the file.
+fn read_line(handle: &Handle) -> anyhow::Result<Option<Variable>> {
+    handle.with("file", |file: &mut OpenFile| {
+        let mut line = String::new();
+
+        if file.reader()?.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+
+        Ok(Some(Variable::Str(line)))
+    })
+}
+
+/// Lazily yields every remaining line of a file handle,
+/// for use in `for lines(file) => line`.
+pub(crate) fn lines(handle: Handle) -> Variable {
+    Variable::Handle(Handle::iterator(std::iter::from_fn(move || {
+        read_line(&handle).transpose()
+    })))
+}
+
+fn as_file(var: Variable) -> anyhow::Result<Handle> {
+    match var {
+        Variable::Handle(handle) if handle.kind == "file" => Ok(handle),
+        Variable::Handle(handle) => bail!(Err::HandleMismatch("file", handle.kind)),
+        any => bail!(Err::VarTypeMismatch(Variable::Str("file handle".to_string()), any)),
+    }
+}
+
+/// Provides file system operations, plus reading from the console.
+/// Every failure is an ordinary error, so it can be handled with `try`.
+pub fn io() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        // read(path), or read(handle) for the rest of an open file.
+        "read" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = match args.into_iter().next().unwrap() {
+                Variable::Handle(handle) => return handle.with("file", |file: &mut OpenFile| {
+                    let mut out = String::new();
+                    file.reader()?.read_to_string(&mut out)?;
+                    Ok(Variable::Str(out))
+                }),
+                any => as_str(any)?,
+            };
+
+            Ok(Variable::Str(
+                fs::read_to_string(&path).with_context(|| format!("Failed to read `{}`", path))?
+            ))
+        }
+        // read_bytes(path), or read_bytes(handle count) for up to
+        // `count` bytes of an open file. Returns an empty array at the end.
+        "read_bytes" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let path = match args.next().unwrap() {
+                Variable::Handle(handle) => {
+                    let count = match args.next() {
+                        Some(Variable::Num(num)) if num >= 0.0 => num as u64,
+                        Some(any) => bail!(Err::VarTypeMismatch(Variable::Num(0.0), any)),
+                        None => bail!(Err::MissingArgs("io.read_bytes".to_string())),
+                    };
+
+                    return handle.with("file", |file: &mut OpenFile| {
+                        let mut out = vec![];
+                        file.reader()?.take(count).read_to_end(&mut out)?;
+                        Ok(from_bytes(&out))
+                    });
+                }
+                any => as_str(any)?,
+            };
+
+            Ok(from_bytes(
+                &fs::read(&path).with_context(|| format!("Failed to read `{}`", path))?
+            ))
+        }
+        // write(path content mode?)
+        // mode is one of "truncate" (the default), "append",
+        // "create_new" or "overwrite".
+        // Also write(handle content) for open files, which were
+        // given their mode by `open`.
+        "write" => |args, _| {
+            expect_args(&args, 2, 3)?;
+            let mut args = args.into_iter();
+            let path = match args.next().unwrap() {
+                Variable::Handle(handle) => {
+                    let content = as_str(args.next().unwrap())?;
+                    if args.next().is_some() {
+                        bail!(Err::ModeOnOpenFile)
+                    }
+                    return handle.with("file", |file: &mut OpenFile| {
+                        file.writer()?.write_all(content.as_bytes())?;
+                        Ok(Variable::Void)
+                    });
+                }
+                any => as_str(any)?,
+            };
+            let content = as_str(args.next().unwrap())?;
+
+            write_to(&path, args.next(), content.as_bytes())
+        }
+        "write_bytes" => |args, _| {
+            expect_args(&args, 2, 3)?;
+            let mut args = args.into_iter();
+            let path = match args.next().unwrap() {
+                Variable::Handle(handle) => {
+                    let content = as_bytes(args.next().unwrap())?;
+                    if args.next().is_some() {
+                        bail!(Err::ModeOnOpenFile)
+                    }
+                    return handle.with("file", |file: &mut OpenFile| {
+                        file.writer()?.write_all(&content)?;
+                        Ok(Variable::Void)
+                    });
+                }
+                any => as_str(any)?,
+            };
+            let content = as_bytes(args.next().unwrap())?;
+
+            write_to(&path, args.next(), &content)
+        }
+        "append" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let path = as_str(args.next().unwrap())?;
+            let content = as_str(args.next().unwrap())?;
+
+            write_to(&path, Some(Variable::Str("append".to_string())), content.as_bytes())
+        }
+        "append_bytes" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let path = as_str(args.next().unwrap())?;
+            let content = as_bytes(args.next().unwrap())?;
+
+            write_to(&path, Some(Variable::Str("append".to_string())), &content)
+        }
+        "exists" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Bool(Path::new(&path).exists()))
+        }
+        "is_file" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Bool(Path::new(&path).is_file()))
+        }
+        "is_dir" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Bool(Path::new(&path).is_dir()))
+        }
+        // Names of everything in a directory, sorted.
+        "list" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            let mut names = vec![];
+            for entry in fs::read_dir(&path).with_context(|| format!("Failed to list `{}`", path))? {
+                names.push(entry?.file_name().to_string_lossy().into_owned());
+            }
+            names.sort();
+
+            Ok(Variable::Array(names.into_iter().map(Variable::Str).collect()))
+        }
+        // Creates the directory along with any missing parents, like `mkdir -p`.
+        "mkdir" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            fs::create_dir_all(&path).with_context(|| format!("Failed to create `{}`", path))?;
+
+            Ok(Variable::Void)
+        }
+        // Removes a file, or a directory and everything inside of it.
+        "remove" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            if Path::new(&path).is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            }
+            .with_context(|| format!("Failed to remove `{}`", path))?;
+
+            Ok(Variable::Void)
+        }
+        "rename" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let from = as_str(args.next().unwrap())?;
+            let to = as_str(args.next().unwrap())?;
+
+            fs::rename(&from, &to).with_context(|| format!("Failed to rename `{}` to `{}`", from, to))?;
+
+            Ok(Variable::Void)
+        }
+        "copy" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let from = as_str(args.next().unwrap())?;
+            let to = as_str(args.next().unwrap())?;
+
+            fs::copy(&from, &to).with_context(|| format!("Failed to copy `{}` to `{}`", from, to))?;
+
+            Ok(Variable::Void)
+        }
+        // Returns a map with `size` (in bytes), `modified` (a Unix
+        // timestamp, in seconds), `is_file`, `is_dir` and `readonly`.
+        "metadata" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            let meta = fs::metadata(&path).with_context(|| format!("Failed to read metadata of `{}`", path))?;
+
+            let modified = match meta.modified() {
+                Ok(time) => Variable::Num(match time.duration_since(UNIX_EPOCH) {
+                    Ok(since) => since.as_secs_f64(),
+                    Err(before) => -before.duration().as_secs_f64(),
+                }),
+                // Not every platform records this.
+                Err(_) => Variable::Void,
+            };
+
+            let mut out = BTreeMap::new();
+            out.insert("size".to_string(), Variable::Num(meta.len() as f64));
+            out.insert("modified".to_string(), modified);
+            out.insert("is_file".to_string(), Variable::Bool(meta.is_file()));
+            out.insert("is_dir".to_string(), Variable::Bool(meta.is_dir()));
+            out.insert("readonly".to_string(), Variable::Bool(meta.permissions().readonly()));
+
+            Ok(Variable::Map(out))
+        }
+        // open(path mode?) -> handle
+        // mode is one of "read" (the default), "write", "append",
+        // "create_new" or "read_write".
+        "open" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let path = as_str(args.next().unwrap())?;
+
+            let mut options = OpenOptions::new();
+            let (read, write) = match args.next().map(as_str).transpose()?.as_deref() {
+                None | Some("read") => (true, false),
+                Some("write") => {
+                    options.create(true).truncate(true);
+                    (false, true)
+                }
+                Some("append") => {
+                    options.create(true).append(true);
+                    (false, true)
+                }
+                Some("create_new") => {
+                    options.create_new(true);
+                    (false, true)
+                }
+                Some("read_write") => {
+                    options.create(true);
+                    (true, true)
+                }
+                Some(any) => bail!(Err::UnknownOpenMode(any.to_string())),
+            };
+
+            let file = options
+                .read(read)
+                .write(write)
+                .open(&path)
+                .with_context(|| format!("Failed to open `{}`", path))?;
+
+            let writer = if write { Some(BufWriter::new(file.try_clone()?)) } else { None };
+
+            Ok(Variable::Handle(Handle::new("file", OpenFile {
+                reader: if read { Some(BufReader::new(file)) } else { None },
+                writer,
+                closed: false,
+            })))
+        }
+        // The next line of an open file, or void at the end of it.
+        "read_line" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let handle = as_file(args.into_iter().next().unwrap())?;
+
+            Ok(read_line(&handle)?.unwrap_or(Variable::Void))
+        }
+        "lines" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(lines(as_file(args.into_iter().next().unwrap())?))
+        }
+        "flush" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let handle = as_file(args.into_iter().next().unwrap())?;
+
+            handle.with("file", |file: &mut OpenFile| {
+                file.writer()?.flush()?;
+                Ok(Variable::Void)
+            })
+        }
+        // Flushes anything left to write. Using the handle
+        // afterwards is an error.
+        "close" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let handle = as_file(args.into_iter().next().unwrap())?;
+
+            handle.with("file", |file: &mut OpenFile| {
+                if let Some(mut writer) = file.writer.take() {
+                    writer.flush()?;
+                }
+                file.reader = None;
+                file.closed = true;
+                Ok(Variable::Void)
+            })
+        }
+        "input" => |args, scope| {
+            if let Some(arg) = args.first() {
+                write!(scope.stdout, "{}", arg)?;
+                scope.stdout.flush()?;
+            }
+
+            let mut out = String::new();
+
+            if scope.stdin.read_line(&mut out).is_ok() {
+                return Ok(Variable::Str(out.trim_end().to_string()));
+            }
+
+            Ok(Variable::Void)
+        }
+    }
+
+    map
+}
@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Number, Value};
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+
+use super::{as_str, expect_args};
+
+/// Objects become maps, and `null` becomes void.
+fn from_json(value: Value) -> Variable {
+    match value {
+        Value::Null => Variable::Void,
+        Value::Bool(b) => Variable::Bool(b),
+        Value::Number(num) => Variable::Num(num.as_f64().unwrap_or(f64::NAN)),
+        Value::String(string) => Variable::Str(string),
+        Value::Array(arr) => Variable::Array(arr.into_iter().map(from_json).collect()),
+        Value::Object(obj) => Variable::Map(
+            obj.into_iter()
+                .map(|(key, x)| (key, from_json(x)))
+                .collect(),
+        ),
+    }
+}
+
+fn to_json(var: &Variable) -> anyhow::Result<Value> {
+    Ok(match var {
+        Variable::Void => Value::Null,
+        Variable::Bool(b) => Value::Bool(*b),
+        // Whole numbers are written without a `.0`.
+        Variable::Num(num) if num.fract() == 0.0 && num.abs() < i64::MAX as f64 => {
+            Value::Number((*num as i64).into())
+        }
+        Variable::Num(num) => match Number::from_f64(*num) {
+            Some(num) => Value::Number(num),
+            None => bail!(Err::Unserializable(var.to_string(), "JSON")),
+        },
+        Variable::Str(string) | Variable::Html(string) => Value::String(string.clone()),
+        Variable::Array(arr) => {
+            Value::Array(arr.iter().map(to_json).collect::<anyhow::Result<_>>()?)
+        }
+        Variable::Map(map) => {
+            let mut obj = Map::new();
+            for (key, x) in map {
+                obj.insert(key.clone(), to_json(x)?);
+            }
+            Value::Object(obj)
+        }
+        any => bail!(Err::Unserializable(any.as_words(), "JSON")),
+    })
+}
+
+/// Reads and writes JSON.
+pub fn json() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        "parse" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let text = as_str(args.into_iter().next().unwrap())?;
+
+            match serde_json::from_str(&text) {
+                Ok(value) => Ok(from_json(value)),
+                Err(err) => {
+                    // serde_json puts the position on the end of its messages.
+                    let message = err.to_string();
+                    let message = match message.rfind(" at line ") {
+                        Some(idx) => message[..idx].to_string(),
+                        None => message,
+                    };
+
+                    bail!(Err::BadJson(err.line(), err.column(), message))
+                }
+            }
+        }
+        // stringify(value pretty?)
+        "stringify" => |args, _| {
+            expect_args(&args, 1, 2)?;
+
+            let pretty = match args.get(1) {
+                Some(Variable::Bool(pretty)) => *pretty,
+                Some(any) => bail!(Err::VarTypeMismatch(Variable::Bool(true), any.clone())),
+                None => false,
+            };
+
+            let value = to_json(&args[0])?;
+
+            Ok(Variable::Str(if pretty {
+                serde_json::to_string_pretty(&value)?
+            } else {
+                value.to_string()
+            }))
+        }
+    }
+
+    map
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+
+use super::{as_map, as_str, expect_args};
+
+/// Provides building and reading maps. Maps are values, so
+/// `set` hands back a changed copy rather than changing the map.
+pub fn map() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        // new(key value key value...) -> a map.
+        // Its values can be read with `my_map.key` or `get`.
+        "new" => |args, _| {
+            if args.len() % 2 != 0 {
+                bail!(Err::IncorrectArgCount(args.len() + 1, args.len()))
+            }
+
+            let mut out = BTreeMap::new();
+            let mut args = args.into_iter();
+            while let (Some(key), Some(value)) = (args.next(), args.next()) {
+                out.insert(as_str(key)?, value);
+            }
+
+            Ok(Variable::Map(out))
+        }
+        // get(map key) -> the value, or void if the key isn't there.
+        "get" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let mut map = as_map(args.next().unwrap())?;
+            let key = as_str(args.next().unwrap())?;
+
+            Ok(map.remove(&key).unwrap_or(Variable::Void))
+        }
+        // set(map key value) -> a copy of the map, with the key set.
+        "set" => |args, _| {
+            expect_args(&args, 3, 3)?;
+            let mut args = args.into_iter();
+            let mut map = as_map(args.next().unwrap())?;
+            let key = as_str(args.next().unwrap())?;
+
+            map.insert(key, args.next().unwrap());
+            Ok(Variable::Map(map))
+        }
+        // keys(map) -> every key, sorted.
+        "keys" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(Variable::Array(
+                as_map(args.into_iter().next().unwrap())?.into_keys().map(Variable::Str).collect()
+            ))
+        }
+        "has" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let map = as_map(args.next().unwrap())?;
+            let key = as_str(args.next().unwrap())?;
+
+            Ok(Variable::Bool(map.contains_key(&key)))
+        }
+    };
+
+    map
+}
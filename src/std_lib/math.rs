@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+
+use super::{as_int, as_num, expect_args};
+
+fn gcd(a: i64, b: i64) -> anyhow::Result<i64> {
+    let (mut x, mut y) = (a.unsigned_abs(), b.unsigned_abs());
+    while y != 0 {
+        let rem = x % y;
+        x = y;
+        y = rem;
+    }
+    // Only `gcd(i64::MIN 0)` and the like end up out of range.
+    match i64::try_from(x) {
+        Ok(out) => Ok(out),
+        Err(_) => bail!(Err::IntegerOverflow("greatest common divisor", a, b)),
+    }
+}
+
+/// Folds two or more integers together with a bitwise operation.
+fn bitwise(args: Vec<Variable>, func: fn(i64, i64) -> i64) -> anyhow::Result<Variable> {
+    expect_args(&args, 2, usize::MAX)?;
+    let mut args = args.into_iter();
+
+    let mut out = as_int(args.next().unwrap())?;
+    for arg in args {
+        out = func(out, as_int(arg)?);
+    }
+
+    Ok(Variable::Num(out as f64))
+}
+
+fn shift(args: Vec<Variable>, func: fn(i64, u32) -> Option<i64>) -> anyhow::Result<Variable> {
+    expect_args(&args, 2, 2)?;
+    let mut args = args.into_iter();
+    let num = as_int(args.next().unwrap())?;
+    let by = as_int(args.next().unwrap())?;
+
+    match u32::try_from(by).ok().and_then(|by| func(num, by)) {
+        Some(out) => Ok(Variable::Num(out as f64)),
+        None => bail!(Err::ShiftOutOfRange(by)),
+    }
+}
+
+/// Provides the usual maths functions, integer helpers
+/// and number formatting.
+pub fn math() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    map.insert("pi".to_string(), Variable::Num(std::f64::consts::PI));
+    map.insert("tau".to_string(), Variable::Num(std::f64::consts::TAU));
+    map.insert("e".to_string(), Variable::Num(std::f64::consts::E));
+    map.insert("inf".to_string(), Variable::Num(f64::INFINITY));
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    /// Functions that take one number and return another.
+    macro_rules! unary_fn {
+        (
+				$(
+					$name: expr => $func: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native(|args, _| {
+                expect_args(&args, 1, 1)?;
+                let func: fn(f64) -> f64 = $func;
+
+                Ok(Variable::Num(func(as_num(args.into_iter().next().unwrap())?)))
+            })); )*
+        };
+    }
+
+    unary_fn! {
+        "sqrt" => f64::sqrt
+        "cbrt" => f64::cbrt
+        "abs" => f64::abs
+        "sign" => f64::signum
+        "floor" => f64::floor
+        "ceil" => f64::ceil
+        "trunc" => f64::trunc
+        "sin" => f64::sin
+        "cos" => f64::cos
+        "tan" => f64::tan
+        "asin" => f64::asin
+        "acos" => f64::acos
+        "atan" => f64::atan
+        "sinh" => f64::sinh
+        "cosh" => f64::cosh
+        "tanh" => f64::tanh
+        "exp" => f64::exp
+        "ln" => f64::ln
+        "log2" => f64::log2
+        "to_radians" => f64::to_radians
+        "to_degrees" => f64::to_degrees
+    }
+
+    insert_fn! {
+        // round(num digits?) -> rounds half away from zero,
+        // optionally to a number of decimal places.
+        "round" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let num = as_num(args.next().unwrap())?;
+
+            Ok(Variable::Num(match args.next() {
+                Some(digits) => {
+                    let scale = 10f64.powi(as_int(digits)? as i32);
+                    (num * scale).round() / scale
+                }
+                None => num.round(),
+            }))
+        }
+        // log(num base?) -> base 10 unless told otherwise.
+        "log" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let num = as_num(args.next().unwrap())?;
+
+            Ok(Variable::Num(match args.next() {
+                Some(base) => num.log(as_num(base)?),
+                None => num.log10(),
+            }))
+        }
+        // atan2(y x)
+        "atan2" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let y = as_num(args.next().unwrap())?;
+            let x = as_num(args.next().unwrap())?;
+
+            Ok(Variable::Num(y.atan2(x)))
+        }
+        "hypot" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let x = as_num(args.next().unwrap())?;
+            let y = as_num(args.next().unwrap())?;
+
+            Ok(Variable::Num(x.hypot(y)))
+        }
+        "min" => |args, _| {
+            expect_args(&args, 1, usize::MAX)?;
+
+            let mut out = f64::INFINITY;
+            for arg in args {
+                out = out.min(as_num(arg)?);
+            }
+
+            Ok(Variable::Num(out))
+        }
+        "max" => |args, _| {
+            expect_args(&args, 1, usize::MAX)?;
+
+            let mut out = f64::NEG_INFINITY;
+            for arg in args {
+                out = out.max(as_num(arg)?);
+            }
+
+            Ok(Variable::Num(out))
+        }
+        // clamp(num min max)
+        "clamp" => |args, _| {
+            expect_args(&args, 3, 3)?;
+            let mut args = args.into_iter();
+            let num = as_num(args.next().unwrap())?;
+            let min = as_num(args.next().unwrap())?;
+            let max = as_num(args.next().unwrap())?;
+
+            Ok(Variable::Num(num.max(min).min(max)))
+        }
+        "is_nan" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(Variable::Bool(as_num(args.into_iter().next().unwrap())?.is_nan()))
+        }
+        "is_integer" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(Variable::Bool(as_num(args.into_iter().next().unwrap())?.fract() == 0.0))
+        }
+        "gcd" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let a = as_int(args.next().unwrap())?;
+            let b = as_int(args.next().unwrap())?;
+
+            Ok(Variable::Num(gcd(a, b)? as f64))
+        }
+        "lcm" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let a = as_int(args.next().unwrap())?;
+            let b = as_int(args.next().unwrap())?;
+
+            let lcm = match gcd(a, b)? {
+                0 => Some(0),
+                gcd => (a / gcd).checked_mul(b).and_then(i64::checked_abs),
+            };
+
+            match lcm {
+                Some(lcm) => Ok(Variable::Num(lcm as f64)),
+                None => bail!(Err::IntegerOverflow("least common multiple", a, b)),
+            }
+        }
+        "bit_and" => |args, _| bitwise(args, |a, b| a & b)
+        "bit_or" => |args, _| bitwise(args, |a, b| a | b)
+        "bit_xor" => |args, _| bitwise(args, |a, b| a ^ b)
+        "bit_not" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(Variable::Num(!as_int(args.into_iter().next().unwrap())? as f64))
+        }
+        "shl" => |args, _| shift(args, i64::checked_shl)
+        "shr" => |args, _| shift(args, i64::checked_shr)
+        // fixed(num digits) -> the number as a string, with exactly
+        // `digits` decimal places.
+        "fixed" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let num = as_num(args.next().unwrap())?;
+            let digits = as_int(args.next().unwrap())?;
+
+            // Far more than an f64 has, but small enough that
+            // formatting can't run out of memory.
+            if !(0..=100).contains(&digits) {
+                bail!(Err::DigitsOutOfRange(digits))
+            }
+
+            Ok(Variable::Str(format!("{:.*}", digits as usize, num)))
+        }
+    };
+
+    map
+}
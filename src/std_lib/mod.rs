@@ -0,0 +1,927 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::data_types::*;
+
+use crate::errors::Err;
+
+use anyhow::bail;
+
+use rayon::prelude::*;
+
+mod config;
+mod csv;
+mod encoding;
+mod format;
+mod html;
+mod internet;
+mod io;
+mod json;
+mod map;
+mod math;
+mod os;
+mod path;
+mod random;
+mod server;
+mod string;
+mod template;
+mod time;
+
+pub fn construct_lib() -> HashMap<String, Variable> {
+    let mut map = HashMap::new();
+
+    map.insert("void".to_string(), Variable::Void);
+
+    map.insert("true".to_string(), Variable::Bool(true));
+    map.insert("false".to_string(), Variable::Bool(false));
+
+    map.insert("num_max".to_string(), Variable::Num(f64::MAX));
+    map.insert("num_min".to_string(), Variable::Num(f64::MIN));
+
+    map.insert("num_pi".to_string(), Variable::Num(std::f64::consts::PI));
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+    insert_fn!(
+        "hello_world" => |_, scope| {
+            writeln!(scope.stdout, "Hello world!")?;
+            Ok(Variable::Void)
+        }
+        "len" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(1, args.len()))
+            }
+            // Strings are measured in characters (Unicode scalar
+            // values), the same as `index` and `chars` count them.
+            Ok(Variable::Num(match args.remove(0) {
+                Variable::Array(arr) => arr.len(),
+                Variable::Str(string) => string.chars().count(),
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Array(vec![]),
+                    any
+                ))
+            } as f64))
+        }
+        // bnd == bounds.
+        // This function returns the length-1, AKA the maximum
+        // bound for indexing. -1 for empty arrays and strings.
+        "bnd" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(1, args.len()))
+            }
+            Ok(Variable::Num(match args.remove(0) {
+                Variable::Array(arr) => arr.len(),
+                Variable::Str(string) => string.chars().count(),
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Array(vec![]),
+                    any
+                ))
+            } as f64 - 1.0))
+        }
+        // The length of a string in bytes, as UTF-8.
+        "byte_len" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(1, args.len()))
+            }
+            Ok(Variable::Num(match args.remove(0) {
+                Variable::Str(string) => string.len(),
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Str("".to_string()),
+                    any
+                ))
+            } as f64))
+        }
+        "pop" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(1, args.len()));
+            }
+
+            Ok(match args.remove(0) {
+                Variable::Array(mut arr) => { arr.pop(); Variable::Array(arr) }
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Array(vec![]),
+                    any
+                ))
+            })
+        }
+        "index" => |mut args, _| {
+            if args.len() != 2 {
+                bail!(Err::IncorrectArgCount(2, args.len()));
+            }
+
+            Ok(match args.remove(0) {
+                Variable::Array(mut arr) => {
+                    let idx = match args.remove(0) {
+                        Variable::Num(num) => num as usize,
+                        any => bail!(Err::VarTypeMismatch(
+                            Variable::Num(0.0),
+                            any
+                        ))
+                    };
+
+                    if idx >= arr.len() {
+                        bail!(Err::OutOfBoundsIndex(arr.len(), idx))
+                    }
+
+                    arr.remove(
+                        idx
+                    )
+                },
+                Variable::Str(string) => {
+                    let idx = match args.remove(0) {
+                        Variable::Num(num) => num as usize,
+                        any => bail!(Err::VarTypeMismatch(
+                            Variable::Num(0.0),
+                            any
+                        ))
+                    };
+
+                    match string.chars().nth(idx) {
+                        Some(char) => Variable::Str(char.into()),
+                        None => bail!(Err::OutOfBoundsIndex(string.chars().count(), idx))
+                    }
+                },
+                any => bail!(Err::VarTypeMismatch(
+                            Variable::Array(vec![]),
+                            any
+                ))
+            })
+        }
+        "index_v" => |mut args, _| {
+            if args.len() != 2 {
+                bail!(Err::IncorrectArgCount(2, args.len()));
+            }
+
+            let idx = match args.remove(1) {
+                Variable::Num(num) => num as usize,
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Num(0.0),
+                    any
+                ))
+            };
+
+            Ok(match args.remove(0) {
+                Variable::Array(mut arr) => {
+                    if idx >= arr.len() {
+                        return Ok(Variable::Void)
+                    }
+
+                    arr.remove(
+                        idx
+                    )
+                },
+                Variable::Str(string) => match string.chars().nth(idx) {
+                    Some(char) => Variable::Str(char.into()),
+                    None => Variable::Void
+                },
+                any => bail!(Err::VarTypeMismatch(
+                            Variable::Array(vec![]),
+                            any
+                ))
+            })
+        }
+        // replace(arr index elem) replaces an element, and
+        // replace(string from to) replaces text.
+        "replace" => |mut args, _| {
+            if let Some(Variable::Str(_)) = args.first() {
+                return string::replace(args);
+            }
+
+            if args.len() != 3 {
+                bail!(
+                    Err::IncorrectArgCount(
+                        3,
+                        args.len()
+                    )
+                )
+            }
+
+            let mut array = match args.remove(0) {
+                Variable::Array(arr) => arr,
+                any => bail!(
+                    Err::VarTypeMismatch(
+                        Variable::Array(vec![]),
+                        any
+                    )
+                )
+            };
+
+            let index = match args.remove(0) {
+                Variable::Num(num) => num as usize,
+                any => bail!(
+                    Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    )
+                )
+            };
+
+            let elem = args.remove(0);
+
+            *match array.get_mut(index) {
+                Some(arr) => arr,
+                None => bail!(Err::OutOfBoundsIndex(array.len(), index))
+            } = elem;
+
+            Ok(Variable::Array(array))
+        }
+        "enumerate" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(
+                    1,
+                    args.len()
+                ))
+            }
+
+            let array = match args.remove(0) {
+                Variable::Array(array) => array,
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Array(vec![]),
+                    any
+                ))
+            };
+
+            let array = array.into_par_iter().enumerate().map(
+                |x| {
+                    Variable::Array(vec![Variable::Num(x.0 as f64), x.1])
+                }
+            );
+
+            Ok(Variable::Array(
+                array.collect()
+            ))
+        }
+        "push" => |args, _| {
+            if args.len() < 2 {
+                bail!(Err::MissingArgs("push".to_string()));
+            }
+
+            let mut args = args.into_iter();
+
+            let mut array = match args.next() {
+                Some(Variable::Array(arr)) => arr,
+                Some(any) => bail!(Err::VarTypeMismatch(
+                    Variable::Array(vec![]),
+                    any
+                )),
+                None => bail!(Err::EOF)
+            };
+
+            for var in args {
+                array.push(var)
+            }
+
+            Ok(Variable::Array(array))
+        }
+        "eq" => |args, _| {
+            let mut args = args.into_iter();
+            let first = args.next().unwrap();
+            for arg in args {
+                if arg != first {
+                    return Ok(Variable::Bool(false))
+                }
+            }
+            Ok(Variable::Bool(true))
+        }
+        "cmp" => |args, _| {
+            if args.len() < 2 {
+                bail!(Err::MissingArgs("cmp".to_string()))
+            }
+
+            let mut args = args.into_iter();
+
+            let to_cmp = args.next().unwrap();
+
+            for arg in args {
+                // PartialOrd only knows how to compare numbers.
+                for var in [&to_cmp, &arg] {
+                    if !matches!(var, Variable::Num(_)) {
+                        bail!(Err::VarTypeMismatch(Variable::Num(0.0), var.clone()))
+                    }
+                }
+                if to_cmp < arg {
+                    return Ok(Variable::Bool(false))
+                }
+            }
+
+            Ok(Variable::Bool(true))
+        }
+        "range" => |mut args, _| {
+            if args.len() != 2 {
+                bail!(Err::IncorrectArgCount(2, args.len()))
+            }
+
+            let num_1 = match args.remove(0) {
+                Variable::Num(num) => num as i32,
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Num(0.0),
+                    any
+                ))
+            };
+
+            let num_2 = match args.remove(0) {
+                Variable::Num(num) => num as i32,
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Num(0.0),
+                    any
+                ))
+            };
+
+            Ok(Variable::Array(
+                (num_1..num_2).into_par_iter().map(
+                    |x| Variable::Num(x as f64)
+                ).collect()
+            ))
+        }
+        "print" => |args, scope| {
+            for i in args {
+                write!(scope.stdout, "{}", i)?
+            }
+            scope.stdout.flush()?;
+            Ok(Variable::Void)
+        }
+        "println" => |args, scope| {
+            for i in args {
+                write!(scope.stdout, "{}", i)?
+            }
+            writeln!(scope.stdout)?;
+            scope.stdout.flush()?;
+            Ok(Variable::Void)
+        }
+        // format("Hello {name}, you are {age}")
+        // See format.rs for everything placeholders can do.
+        "format" => format::format
+        // Stays HTML if any of the values are HTML, see `html::concat`.
+        "con" => |args, _| {
+            if args.is_empty() {
+                bail!(Err::MissingArgs("con".to_string()))
+            }
+
+            Ok(html::concat(args))
+        }
+        "add" => |args, _| {
+            let mut final_out = 0.0;
+
+            for arg in args {
+                match arg {
+                    Variable::Num(num) => final_out += num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                }
+            }
+
+            Ok(Variable::Num(final_out))
+        }
+        "sub" => |args, _| {
+            let mut args = args.into_iter();
+
+            let mut final_out = match args.next() {
+                Some(var) => match var {
+                    Variable::Num(num) => num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                },
+                None => bail!(Err::MissingArgs("sub".to_string()))
+            };
+
+            for arg in args {
+                match arg {
+                    Variable::Num(num) => final_out -= num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                }
+            }
+
+            Ok(Variable::Num(final_out))
+        }
+        "mod" => |args, _| {
+            let mut args = args.into_iter();
+
+            let mut final_out = match args.next() {
+                Some(var) => match var {
+                    Variable::Num(num) => num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                },
+                None => bail!(Err::MissingArgs("sub".to_string()))
+            };
+
+            for arg in args {
+                match arg {
+                    Variable::Num(num) => final_out %= num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                }
+            }
+
+            Ok(Variable::Num(final_out))
+        }
+        "mult" => |args, _| {
+            let mut args = args.into_iter();
+
+            let mut final_out = match args.next() {
+                Some(var) => match var {
+                    Variable::Num(num) => num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                },
+                None => bail!(Err::MissingArgs("mult".to_string()))
+            };
+
+            for arg in args {
+                match arg {
+                    Variable::Num(num) => final_out *= num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                }
+            }
+
+            Ok(Variable::Num(final_out))
+        }
+        "div" => |args, _| {
+            let mut args = args.into_iter();
+
+            let mut final_out = match args.next() {
+                Some(var) => match var {
+                    Variable::Num(num) => num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                },
+                None => bail!(Err::MissingArgs("div".to_string()))
+            };
+
+            for arg in args {
+                match arg {
+                    Variable::Num(num) => final_out /= num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                }
+            }
+
+            Ok(Variable::Num(final_out))
+        }
+        "xor" => |args, _| {
+            let mut args = args.into_iter();
+
+            let mut final_out = match args.next() {
+                Some(var) => match var {
+                    Variable::Num(num) => num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                },
+                None => bail!(Err::MissingArgs("div".to_string()))
+            };
+
+            for arg in args {
+                match arg {
+                    Variable::Num(num) => final_out = (final_out as i32 ^ num as i32) as f64,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                }
+            }
+
+            Ok(Variable::Num(final_out))
+        }
+        "pow" => |args, _| {
+            let mut args = args.into_iter();
+
+            let mut final_out = match args.next() {
+                Some(var) => match var {
+                    Variable::Num(num) => num,
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                },
+                None => bail!(Err::MissingArgs("div".to_string()))
+            };
+
+            for arg in args {
+                match arg {
+                    Variable::Num(num) => final_out = final_out.powf(num),
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Num(0.0),
+                        any
+                    ))
+                }
+            }
+
+            Ok(Variable::Num(final_out))
+        }
+        "not" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(1, args.len()))
+            }
+
+            Ok(match args.remove(0) {
+                Variable::Bool(boolean) => Variable::Bool(!boolean),
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Bool(true),
+                    any
+                ))
+            })
+        }
+        "str" => |args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(
+                    1,
+                    args.len()
+                ))
+            }
+
+            Ok(Variable::Str(
+                args[0].to_string()
+            ))
+        }
+        "num" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(
+                    1,
+                    args.len()
+                ))
+            }
+
+            Ok(Variable::Num(match args.remove(0) {
+                Variable::Str(string) => match string.trim().parse() {
+                    Ok(res) => res,
+                    Err(_) => bail!(Err::NumParserError(Variable::Str(string)))
+                },
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Str("".to_string()),
+                    any
+                ))
+            }))
+        }
+        "weak_eq" => |args, _| {
+            if args.len() < 2 {
+                bail!(Err::MissingArgs("weak_eq".to_string()))
+            }
+            let mut args = args.into_iter();
+
+            let first = args.next().unwrap();
+
+            for arg in args {
+                if arg.to_string() != first.to_string() {
+                    return Ok(Variable::Bool(false))
+                }
+            }
+
+            Ok(Variable::Bool(true))
+        }
+        "chars" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(
+                    1,
+                    args.len()
+                ))
+            }
+
+            Ok(Variable::Array(
+                match args.remove(0) {
+                    Variable::Str(string) => string.par_chars().map(|x| Variable::Str(String::from(x))).collect(),
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Str("".to_string()),
+                        any
+                    ))
+                }
+            ))
+        }
+        "bytes" => |mut args, _| {
+            if args.len() != 1 {
+                bail!(Err::IncorrectArgCount(
+                    1,
+                    args.len()
+                ))
+            }
+
+            Ok(Variable::Array(
+                match args.remove(0) {
+                    Variable::Str(string) => string.par_bytes().map(|x| Variable::Num(
+                        x.into()
+                    )).collect(),
+                    any => bail!(Err::VarTypeMismatch(
+                        Variable::Str("".to_string()),
+                        any
+                    ))
+                }
+            ))
+        }
+        // byte_index(string index) -> the byte at a byte
+        // index of the string's UTF-8, as a number.
+        "byte_index" => |mut args, _| {
+            if args.len() != 2 {
+                bail!(Err::IncorrectArgCount(2, args.len()));
+            }
+
+            let string = match args.remove(0) {
+                Variable::Str(string) => string,
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Str("".to_string()),
+                    any
+                ))
+            };
+
+            let idx = match args.remove(0) {
+                Variable::Num(num) => num as usize,
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Num(0.0),
+                    any
+                ))
+            };
+
+            match string.as_bytes().get(idx) {
+                Some(byte) => Ok(Variable::Num((*byte).into())),
+                None => bail!(Err::OutOfBoundsIndex(string.len(), idx))
+            }
+        }
+        // Used by `shrimp test`, but work anywhere.
+        // An optional last argument is added to the failure message.
+        "assert" => |args, _| {
+            let mut args = args.into_iter();
+
+            let cond = match args.next() {
+                Some(Variable::Bool(boolean)) => boolean,
+                Some(any) => bail!(Err::VarTypeMismatch(Variable::Bool(true), any)),
+                None => bail!(Err::MissingArgs("assert".to_string()))
+            };
+
+            if !cond {
+                bail!(Err::AssertionFailed(match args.next() {
+                    Some(msg) => msg.to_string(),
+                    None => "condition was false".to_string()
+                }))
+            }
+
+            Ok(Variable::Void)
+        }
+        "assert_eq" => |args, _| {
+            if args.len() < 2 {
+                bail!(Err::MissingArgs("assert_eq".to_string()))
+            }
+
+            let mut args = args.into_iter();
+            let left = args.next().unwrap();
+            let right = args.next().unwrap();
+
+            if left != right {
+                bail!(Err::AssertionFailed(format!(
+                    "`left == right`{}\n  left: {}\n right: {}",
+                    match args.next() {
+                        Some(msg) => format!(" ({})", msg),
+                        None => String::new()
+                    },
+                    left,
+                    right
+                )))
+            }
+
+            Ok(Variable::Void)
+        }
+        "assert_ne" => |args, _| {
+            if args.len() < 2 {
+                bail!(Err::MissingArgs("assert_ne".to_string()))
+            }
+
+            let mut args = args.into_iter();
+            let left = args.next().unwrap();
+            let right = args.next().unwrap();
+
+            if left == right {
+                bail!(Err::AssertionFailed(format!(
+                    "`left != right`{}\n  left: {}\n right: {}",
+                    match args.next() {
+                        Some(msg) => format!(" ({})", msg),
+                        None => String::new()
+                    },
+                    left,
+                    right
+                )))
+            }
+
+            Ok(Variable::Void)
+        }
+        // Stops the program. Handed back to whoever called it as
+        // `Err::Exit`, instead of taking the whole process down.
+        "exit" => |args, _| {
+            bail!(Err::Exit(
+                match args.first() {
+                    Some(Variable::Num(number)) => *number as i32,
+                    Some(_) => 1,
+                    None => 0
+                }
+            ))
+        }
+        "split" => |args, _| {
+            if args.len() != 2 {
+                bail!(
+                    Err::IncorrectArgCount(
+                        2,
+                        args.len()
+                    )
+                )
+            }
+
+            let to_split = match args.first().unwrap() {
+                Variable::Str(string) => string,
+                any => bail!(
+                    Err::VarTypeMismatch(
+                        Variable::Str("".to_string()),
+                        any.clone()
+                    )
+                )
+            };
+
+            let splitter = match args.get(1).unwrap() {
+                Variable::Str(string) => string,
+                any => bail!(
+                    Err::VarTypeMismatch(
+                        Variable::Str("".to_string()),
+                        any.clone()
+                    )
+                )
+            };
+
+            Ok(Variable::Array(to_split.split(splitter).map(
+                |x| Variable::Str(x.to_string())
+            ).collect()))
+        }
+        // lines(file) lazily yields each line of a file handle from `io.open`,
+        // lines(string) splits text into an array of lines.
+        "lines" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            match args.into_iter().next().unwrap() {
+                Variable::Handle(handle) => Ok(io::lines(handle)),
+                Variable::Str(string) => Ok(string::lines(&string)),
+                any => bail!(Err::VarTypeMismatch(Variable::Str("".to_string()), any)),
+            }
+        }
+    );
+
+    map
+}
+
+/// Names of the libraries built into the interpreter.
+pub const BUILTIN_LIBS: [&str; 16] = [
+    "io", "internet", "html", "path", "os", "time", "math", "random", "string", "server",
+    "template", "json", "csv", "config", "encoding", "map",
+];
+
+pub fn is_builtin(lib: &str) -> bool {
+    BUILTIN_LIBS.contains(&lib)
+}
+
+/// A built-in library, as handed to `use`.
+pub struct Library {
+    pub members: BTreeMap<String, Variable>,
+    /// Before namespaces, `use` dumped every member straight into the
+    /// global scope with a prefix (`internet_get`, `el_p`...). This is
+    /// that prefix, and the members that existed back then. Those
+    /// names still work, but are deprecated, so members added since
+    /// don't get one. `json` was asked for as `json_parse` and
+    /// `json_stringify`, so it has them too.
+    pub legacy: Option<(&'static str, &'static [&'static str])>,
+}
+
+const LEGACY_IO: &[&str] = &["input", "read", "write"];
+
+const LEGACY_INTERNET: &[&str] = &["get", "post"];
+
+const LEGACY_HTML: &[&str] = &[
+    "a", "body", "code", "comment", "custom", "div", "h1", "h2", "h3", "h4", "h5", "h6", "head",
+    "header", "img", "link", "meta", "p", "pre", "samp", "script", "span", "tag", "title",
+];
+
+const LEGACY_JSON: &[&str] = &["parse", "stringify"];
+
+/// Builds the built-in library called `lib`, if there is one.
+pub fn library(lib: &str) -> Option<Library> {
+    let (members, legacy) = match lib {
+        "io" => (io::io(), Some(("io_", LEGACY_IO))),
+        "internet" => (internet::internet(), Some(("internet_", LEGACY_INTERNET))),
+        "html" => (html::html(), Some(("el_", LEGACY_HTML))),
+        "path" => (path::path(), None),
+        "os" => (os::os(), None),
+        "time" => (time::time(), None),
+        "math" => (math::math(), None),
+        "random" => (random::random(), None),
+        "string" => (string::string(), None),
+        "server" => (server::server(), None),
+        "template" => (template::template(), None),
+        "json" => (json::json(), Some(("json_", LEGACY_JSON))),
+        "csv" => (csv::csv(), None),
+        "config" => (config::config(), None),
+        "encoding" => (encoding::encoding(), None),
+        "map" => (map::map(), None),
+        _ => return None,
+    };
+
+    Some(Library { members, legacy })
+}
+
+// Argument helpers for the libraries. They fail with the same
+// errors as the hand-written matches in `construct_lib`.
+
+pub(crate) fn expect_args(args: &[Variable], min: usize, max: usize) -> anyhow::Result<()> {
+    if args.len() < min || args.len() > max {
+        bail!(Err::IncorrectArgCount(
+            if args.len() < min { min } else { max },
+            args.len()
+        ))
+    }
+    Ok(())
+}
+
+/// HTML counts as a string too, so that pages can be written to files.
+pub(crate) fn as_str(var: Variable) -> anyhow::Result<String> {
+    match var {
+        Variable::Str(string) | Variable::Html(string) => Ok(string),
+        any => bail!(Err::VarTypeMismatch(Variable::Str("".to_string()), any)),
+    }
+}
+
+pub(crate) fn as_num(var: Variable) -> anyhow::Result<f64> {
+    match var {
+        Variable::Num(num) => Ok(num),
+        any => bail!(Err::VarTypeMismatch(Variable::Num(0.0), any)),
+    }
+}
+
+/// A duration in milliseconds, as taken by `time.sleep` and timeouts.
+/// Negative durations count as zero.
+pub(crate) fn as_duration(var: Variable) -> anyhow::Result<std::time::Duration> {
+    let ms = as_num(var)?;
+
+    match std::time::Duration::try_from_secs_f64(ms.max(0.0) / 1000.0) {
+        Ok(duration) => Ok(duration),
+        Err(_) => bail!(Err::InvalidDuration(ms)),
+    }
+}
+
+/// Numbers are floats, so integer-only functions check
+/// that they were actually given whole numbers.
+pub(crate) fn as_int(var: Variable) -> anyhow::Result<i64> {
+    match var {
+        Variable::Num(num) if num.fract() == 0.0 && num.abs() <= i64::MAX as f64 => Ok(num as i64),
+        any => bail!(Err::NotAnInteger(any)),
+    }
+}
+
+pub(crate) fn as_map(var: Variable) -> anyhow::Result<BTreeMap<String, Variable>> {
+    match var {
+        Variable::Map(map) => Ok(map),
+        any => bail!(Err::VarTypeMismatch(Variable::Map(BTreeMap::new()), any)),
+    }
+}
+
+pub(crate) fn as_array(var: Variable) -> anyhow::Result<Vec<Variable>> {
+    match var {
+        Variable::Array(arr) => Ok(arr),
+        any => bail!(Err::VarTypeMismatch(Variable::Array(vec![]), any)),
+    }
+}
+
+/// Bytes are represented the same way `bytes` returns them:
+/// an array of numbers from 0 to 255.
+pub(crate) fn as_bytes(var: Variable) -> anyhow::Result<Vec<u8>> {
+    as_array(var)?
+        .into_iter()
+        .map(|x| match x {
+            Variable::Num(num) if (0.0..=255.0).contains(&num) && num.fract() == 0.0 => Ok(num as u8),
+            any => bail!(Err::NotAByte(any)),
+        })
+        .collect()
+}
+
+pub(crate) fn from_bytes(bytes: &[u8]) -> Variable {
+    Variable::Array(bytes.iter().map(|x| Variable::Num(*x as f64)).collect())
+}
@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::thread::JoinHandle;
+
+use crate::data_types::*;
+use crate::errors::Err;
+use crate::scope::Scope;
+
+use anyhow::Context;
+
+use super::{as_array, as_str, expect_args};
+
+/// Builds a command from a program name and an optional array of arguments.
+/// It sees the environment as changed by `set_env` and `remove_env`.
+fn command(
+    args: &mut impl Iterator<Item = Variable>,
+    scope: &Scope,
+) -> anyhow::Result<(String, Command)> {
+    let program = as_str(args.next().unwrap())?;
+    let mut command = Command::new(&program);
+
+    for (name, value) in &scope.env {
+        match value {
+            Some(value) => command.env(name, value),
+            None => command.env_remove(name),
+        };
+    }
+
+    if let Some(arr) = args.next() {
+        for arg in as_array(arr)? {
+            command.arg(as_str(arg)?);
+        }
+    }
+
+    Ok((program, command))
+}
+
+/// A running child process, as handed out by `os.stream`.
+/// Its stderr is collected on another thread, so that it can't
+/// fill up and block the child while stdout is being read.
+struct ChildLines {
+    program: String,
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+    stderr: Option<JoinHandle<String>>,
+    done: bool,
+}
+
+impl Iterator for ChildLines {
+    type Item = anyhow::Result<Variable>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut line = String::new();
+
+        match self.stdout.read_line(&mut line) {
+            Ok(0) => {}
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                return Some(Ok(Variable::Str(line)));
+            }
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err.into()));
+            }
+        }
+
+        // The output has ended, so the child has (or is about to)
+        // exit. A failure only shows up once every line has been read.
+        self.done = true;
+
+        let stderr = match self.stderr.take().map(JoinHandle::join) {
+            Some(Ok(stderr)) => stderr.trim_end().to_string(),
+            _ => String::new(),
+        };
+
+        match self.child.wait() {
+            Ok(status) if status.success() => None,
+            Ok(status) => Some(Err(Err::CommandFailed(self.program.clone(), status, stderr).into())),
+            Err(err) => Some(Err(err.into())),
+        }
+    }
+}
+
+impl Drop for ChildLines {
+    /// A loop that stops early leaves the child running,
+    /// so it is killed rather than left behind.
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// Provides environment variables, the working directory,
+/// and running other programs.
+pub fn os() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        // env(name) -> the variable's value, or void if it isn't set.
+        "env" => |args, scope| {
+            expect_args(&args, 1, 1)?;
+            let name = as_str(args.into_iter().next().unwrap())?;
+
+            let value = match scope.env.get(&name) {
+                Some(value) => value.clone(),
+                None => std::env::var(name).ok(),
+            };
+
+            Ok(value.map(Variable::Str).unwrap_or(Variable::Void))
+        }
+        // set_env and remove_env only affect this program and the
+        // programs it runs, not the rest of the process.
+        "set_env" => |args, scope| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let name = as_str(args.next().unwrap())?;
+            let value = as_str(args.next().unwrap())?;
+
+            scope.env.insert(name, Some(value));
+            Ok(Variable::Void)
+        }
+        "remove_env" => |args, scope| {
+            expect_args(&args, 1, 1)?;
+            let name = as_str(args.into_iter().next().unwrap())?;
+
+            scope.env.insert(name, None);
+            Ok(Variable::Void)
+        }
+        // Every environment variable, as a map.
+        "env_vars" => |args, scope| {
+            expect_args(&args, 0, 0)?;
+
+            let mut vars: BTreeMap<String, String> = std::env::vars().collect();
+            for (name, value) in &scope.env {
+                match value {
+                    Some(value) => vars.insert(name.clone(), value.clone()),
+                    None => vars.remove(name),
+                };
+            }
+
+            Ok(Variable::Map(
+                vars.into_iter().map(|(name, value)| (name, Variable::Str(value))).collect()
+            ))
+        }
+        "cwd" => |args, _| {
+            expect_args(&args, 0, 0)?;
+
+            Ok(Variable::Str(
+                std::env::current_dir()
+                    .context("Failed to get the current directory")?
+                    .to_string_lossy()
+                    .into_owned()
+            ))
+        }
+        "set_cwd" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            std::env::set_current_dir(&path)
+                .with_context(|| format!("Failed to change directory to `{}`", path))?;
+            Ok(Variable::Void)
+        }
+        // run(program args? stdin?) -> { code, stdout, stderr }
+        // Waits for the program to finish. `code` is void if it was
+        // killed by a signal.
+        "run" => |args, scope| {
+            expect_args(&args, 1, 3)?;
+            let mut args = args.into_iter();
+            let (program, mut command) = command(&mut args, scope)?;
+            let input = args.next().map(as_str).transpose()?;
+
+            let mut child = command
+                .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .with_context(|| format!("Failed to run `{}`", program))?;
+
+            // Written from another thread, so that a child filling up
+            // its stdout before reading all of its stdin can't deadlock.
+            let writer = match (child.stdin.take(), input) {
+                (Some(mut stdin), Some(input)) => {
+                    Some(std::thread::spawn(move || stdin.write_all(input.as_bytes())))
+                }
+                _ => None,
+            };
+
+            let output = child
+                .wait_with_output()
+                .with_context(|| format!("Failed to run `{}`", program))?;
+
+            if let Some(writer) = writer {
+                // A child that exits without reading its input is not an error.
+                let _ = writer.join();
+            }
+
+            let mut res = BTreeMap::new();
+            res.insert(
+                "code".to_string(),
+                match output.status.code() {
+                    Some(code) => Variable::Num(code as f64),
+                    None => Variable::Void,
+                },
+            );
+            res.insert(
+                "stdout".to_string(),
+                Variable::Str(String::from_utf8_lossy(&output.stdout).into_owned()),
+            );
+            res.insert(
+                "stderr".to_string(),
+                Variable::Str(String::from_utf8_lossy(&output.stderr).into_owned()),
+            );
+
+            Ok(Variable::Map(res))
+        }
+        // stream(program args?) -> each line of the program's output,
+        // as it is printed. Fails at the end if the program did, with
+        // whatever it wrote to stderr.
+        "stream" => |args, scope| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let (program, mut command) = command(&mut args, scope)?;
+
+            let mut child = command
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .with_context(|| format!("Failed to run `{}`", program))?;
+
+            let stdout = BufReader::new(child.stdout.take().unwrap());
+            let mut pipe = child.stderr.take().unwrap();
+            let stderr = std::thread::spawn(move || {
+                let mut out = vec![];
+                let _ = pipe.read_to_end(&mut out);
+                String::from_utf8_lossy(&out).into_owned()
+            });
+
+            Ok(Variable::Handle(Handle::iterator(ChildLines {
+                program,
+                child,
+                stdout,
+                stderr: Some(stderr),
+                done: false,
+            })))
+        }
+    };
+
+    map
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::data_types::*;
+
+use anyhow::Context;
+
+use super::{as_str, expect_args};
+
+fn path_var(path: &Path) -> Variable {
+    Variable::Str(path.to_string_lossy().into_owned())
+}
+
+/// Turns an optional part of a path into a string, or void if it's missing.
+fn part(part: Option<impl AsRef<Path>>) -> Variable {
+    match part {
+        Some(part) => path_var(part.as_ref()),
+        None => Variable::Void,
+    }
+}
+
+/// Resolves `.` and `..` without touching the file system,
+/// so unlike `canonicalize` it works on paths that don't exist yet.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                // `/..` is just `/`.
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            any => out.push(any),
+        }
+    }
+
+    if out.as_os_str().is_empty() {
+        out.push(".");
+    }
+
+    out
+}
+
+/// Provides path manipulation, and globbing.
+/// Paths are plain strings, so they work with every `io` function.
+pub fn path() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        // join(path parts...)
+        "join" => |args, _| {
+            expect_args(&args, 1, usize::MAX)?;
+
+            let mut out = PathBuf::new();
+            for arg in args {
+                out.push(as_str(arg)?);
+            }
+
+            Ok(path_var(&out))
+        }
+        "parent" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(part(Path::new(&path).parent()))
+        }
+        "file_name" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(part(Path::new(&path).file_name()))
+        }
+        "stem" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(part(Path::new(&path).file_stem()))
+        }
+        "extension" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(part(Path::new(&path).extension()))
+        }
+        "with_extension" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let path = as_str(args.next().unwrap())?;
+            let extension = as_str(args.next().unwrap())?;
+
+            Ok(path_var(&Path::new(&path).with_extension(extension)))
+        }
+        "is_absolute" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Bool(Path::new(&path).is_absolute()))
+        }
+        "canonicalize" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(path_var(
+                &Path::new(&path)
+                    .canonicalize()
+                    .with_context(|| format!("Failed to canonicalize `{}`", path))?
+            ))
+        }
+        "normalize" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            Ok(path_var(&normalize(Path::new(&path))))
+        }
+        // glob("src/**/*.imp") -> every matching path, sorted.
+        "glob" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let pattern = as_str(args.into_iter().next().unwrap())?;
+
+            let paths = glob::glob(&pattern)
+                .with_context(|| format!("Invalid glob pattern `{}`", pattern))?;
+
+            let mut out = vec![];
+            for path in paths {
+                out.push(path_var(&path?));
+            }
+
+            Ok(Variable::Array(out))
+        }
+    };
+
+    map
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::data_types::*;
+use crate::errors::Err;
+use crate::scope::Scope;
+
+use anyhow::bail;
+
+use super::{as_array, as_int, as_num, expect_args};
+
+/// The generator used when a function isn't handed one. Seeded
+/// from the OS, unless `random.seed` says otherwise.
+fn default_rng(scope: &mut Scope) -> &mut StdRng {
+    scope.rng.get_or_insert_with(StdRng::from_entropy)
+}
+
+/// Every function takes an optional generator (from `random.generator`)
+/// as its first argument. Runs `func` with that one, or the default one,
+/// and the rest of the arguments.
+fn with_rng(
+    mut args: Vec<Variable>,
+    scope: &mut Scope,
+    func: impl FnOnce(&mut StdRng, Vec<Variable>) -> anyhow::Result<Variable>,
+) -> anyhow::Result<Variable> {
+    match args.first() {
+        Some(Variable::Handle(handle)) if handle.kind == "random" => {
+            let handle = handle.clone();
+            args.remove(0);
+            handle.with("random", |rng: &mut StdRng| func(rng, args))
+        }
+        _ => func(default_rng(scope), args),
+    }
+}
+
+/// Provides random numbers, and random picks from arrays.
+pub fn random() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        // seed(num) -> makes the default generator reproducible.
+        "seed" => |args, scope| {
+            expect_args(&args, 1, 1)?;
+            let seed = as_int(args.into_iter().next().unwrap())?;
+
+            *default_rng(scope) = StdRng::seed_from_u64(seed as u64);
+            Ok(Variable::Void)
+        }
+        // generator(seed?) -> a generator of its own, which can be
+        // handed to any other function.
+        "generator" => |args, _| {
+            expect_args(&args, 0, 1)?;
+
+            let rng = match args.into_iter().next() {
+                Some(seed) => StdRng::seed_from_u64(as_int(seed)? as u64),
+                None => StdRng::from_entropy(),
+            };
+
+            Ok(Variable::Handle(Handle::new("random", rng)))
+        }
+        // float() -> a number from 0 up to (but not including) 1.
+        "float" => |args, scope| with_rng(args, scope, |rng, args| {
+            expect_args(&args, 0, 0)?;
+
+            Ok(Variable::Num(rng.gen_range(0.0..1.0)))
+        })
+        // int(min max) -> a whole number from min to max, inclusive.
+        "int" => |args, scope| with_rng(args, scope, |rng, args| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let min = as_int(args.next().unwrap())?;
+            let max = as_int(args.next().unwrap())?;
+
+            if min > max {
+                bail!(Err::EmptyRange(min, max))
+            }
+
+            Ok(Variable::Num(rng.gen_range(min..=max) as f64))
+        })
+        // bool(chance?) -> true with the given chance, from 0 to 1.
+        "bool" => |args, scope| with_rng(args, scope, |rng, args| {
+            expect_args(&args, 0, 1)?;
+
+            let chance = match args.into_iter().next() {
+                // NaN counts as no chance at all.
+                Some(chance) => match as_num(chance)? {
+                    chance if chance.is_nan() => 0.0,
+                    chance => chance.clamp(0.0, 1.0),
+                },
+                None => 0.5,
+            };
+
+            Ok(Variable::Bool(rng.gen_bool(chance)))
+        })
+        // choice(arr) -> one element, or void if the array is empty.
+        "choice" => |args, scope| with_rng(args, scope, |rng, args| {
+            expect_args(&args, 1, 1)?;
+            let arr = as_array(args.into_iter().next().unwrap())?;
+
+            Ok(arr.choose(rng).cloned().unwrap_or(Variable::Void))
+        })
+        // shuffle(arr) -> a shuffled copy.
+        "shuffle" => |args, scope| with_rng(args, scope, |rng, args| {
+            expect_args(&args, 1, 1)?;
+            let mut arr = as_array(args.into_iter().next().unwrap())?;
+
+            arr.shuffle(rng);
+            Ok(Variable::Array(arr))
+        })
+        // sample(arr count) -> `count` different elements, in random
+        // order. Fewer if the array doesn't have that many.
+        "sample" => |args, scope| with_rng(args, scope, |rng, args| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let arr = as_array(args.next().unwrap())?;
+            let count = as_int(args.next().unwrap())?.max(0) as usize;
+
+            Ok(Variable::Array(arr.choose_multiple(rng, count).cloned().collect()))
+        })
+    };
+
+    map
+}
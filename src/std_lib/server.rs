@@ -0,0 +1,451 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::data_types::*;
+use crate::errors::Err;
+use crate::interpreter;
+use crate::scope::Scope;
+
+use anyhow::bail;
+use anyhow::Context;
+
+use super::encoding::{parse_query, url_decode};
+use super::{as_bytes, as_map, as_str, expect_args};
+
+/// Requests with bigger headers or bodies than these are turned away.
+const MAX_HEAD: usize = 64 * 1024;
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// Requests are handled one at a time, so a client that connects and
+/// never finishes its request is only waited for this long.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait before accepting again, after failing to.
+const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
+enum Segment {
+    Literal(String),
+    /// `:name`, which matches any one segment.
+    Param(String),
+    /// `*`, which matches everything that's left.
+    Rest,
+}
+
+#[derive(Clone)]
+struct Route {
+    method: String,
+    pattern: Vec<Segment>,
+    handler: Variable,
+}
+
+/// A server, as handed out by `server.new`.
+#[derive(Default)]
+struct Server {
+    routes: Vec<Route>,
+}
+
+/// A request, as read off the socket.
+struct RawRequest {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: u16, body: impl Into<String>) -> Self {
+        Response {
+            status,
+            headers: vec![("content-type".to_string(), "text/plain; charset=utf-8".to_string())],
+            body: body.into().into_bytes(),
+        }
+    }
+}
+
+fn parse_pattern(pattern: &str) -> anyhow::Result<Vec<Segment>> {
+    let segments: Vec<&str> = pattern.split('/').filter(|x| !x.is_empty()).collect();
+    let last = segments.len().saturating_sub(1);
+
+    segments
+        .into_iter()
+        .enumerate()
+        .map(|(idx, segment)| match segment {
+            "*" if idx == last => Ok(Segment::Rest),
+            "*" => bail!(Err::BadRoutePattern(pattern.to_string())),
+            param if param.starts_with(':') => Ok(Segment::Param(param[1..].to_string())),
+            literal => Ok(Segment::Literal(literal.to_string())),
+        })
+        .collect()
+}
+
+/// Matches a path against a route's pattern, returning its params if it fits.
+fn match_path(pattern: &[Segment], path: &str) -> Option<BTreeMap<String, Variable>> {
+    let mut segments = path.split('/').filter(|x| !x.is_empty());
+    let mut params = BTreeMap::new();
+
+    for segment in pattern {
+        match segment {
+            Segment::Rest => {
+                let rest: Vec<String> = segments
+                    .by_ref()
+                    .map(|x| url_decode(x).ok())
+                    .collect::<Option<_>>()?;
+                params.insert("*".to_string(), Variable::Str(rest.join("/")));
+            }
+            Segment::Param(name) => {
+                params.insert(name.clone(), Variable::Str(url_decode(segments.next()?).ok()?));
+            }
+            Segment::Literal(literal) => {
+                if url_decode(segments.next()?).ok()? != *literal {
+                    return None;
+                }
+            }
+        }
+    }
+
+    match segments.next() {
+        Some(_) => None,
+        None => Some(params),
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> anyhow::Result<Option<RawRequest>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+
+    let head_end = loop {
+        if let Some(pos) = buf.windows(4).position(|x| x == b"\r\n\r\n") {
+            break pos;
+        }
+        if buf.len() > MAX_HEAD {
+            bail!("The request's headers are too large")
+        }
+
+        match stream.read(&mut chunk).await? {
+            0 if buf.is_empty() => return Ok(None),
+            0 => bail!("The connection closed part-way through the request"),
+            read => buf.extend_from_slice(&chunk[..read]),
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let (method, target) = match (request_line.next(), request_line.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => bail!("Malformed request line"),
+    };
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length = match headers.iter().find(|(name, _)| name == "content-length") {
+        Some((_, length)) => length.parse::<usize>().context("Malformed content-length")?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        bail!("The request's body is too large")
+    }
+
+    let mut body = buf.split_off(head_end + 4);
+    while body.len() < length {
+        match stream.read(&mut chunk).await? {
+            0 => bail!("The connection closed part-way through the request"),
+            read => body.extend_from_slice(&chunk[..read]),
+        }
+    }
+    body.truncate(length);
+
+    Ok(Some(RawRequest {
+        method,
+        target,
+        headers,
+        body,
+    }))
+}
+
+async fn write_response(stream: &mut TcpStream, res: Response) -> std::io::Result<()> {
+    let reason = StatusCode::from_u16(res.status)
+        .ok()
+        .and_then(|x| x.canonical_reason())
+        .unwrap_or("");
+
+    let mut out = format!("HTTP/1.1 {} {}\r\n", res.status, reason);
+    for (name, value) in &res.headers {
+        out += &format!("{}: {}\r\n", name, value);
+    }
+    out += &format!("content-length: {}\r\nconnection: close\r\n\r\n", res.body.len());
+
+    stream.write_all(out.as_bytes()).await?;
+    stream.write_all(&res.body).await?;
+    stream.shutdown().await
+}
+
+/// Turns whatever a handler returned into a response:
+/// - a string is sent as HTML
+/// - a map can set the `status`, `headers` and `body`
+/// - void is an empty `204 No Content`
+fn into_response(var: Variable) -> anyhow::Result<Response> {
+    let mut res = Response {
+        status: 200,
+        headers: vec![],
+        body: vec![],
+    };
+
+    let body = match var {
+        Variable::Map(mut map) => {
+            if let Some(status) = map.remove("status") {
+                res.status = match status {
+                    Variable::Num(num) if (100.0..1000.0).contains(&num) => num as u16,
+                    any => bail!(Err::VarTypeMismatch(Variable::Num(200.0), any)),
+                };
+            }
+            if let Some(headers) = map.remove("headers") {
+                for (name, value) in as_map(headers)? {
+                    let value = value.to_string();
+                    // Otherwise a header could end the head early,
+                    // and smuggle in headers or a body of its own.
+                    if name.contains(['\r', '\n']) || value.contains(['\r', '\n']) {
+                        bail!(Err::BadHeader(name))
+                    }
+                    res.headers.push((name.to_lowercase(), value));
+                }
+            }
+            map.remove("body").unwrap_or(Variable::Void)
+        }
+        Variable::Void => {
+            res.status = 204;
+            Variable::Void
+        }
+        any => any,
+    };
+
+    res.body = match body {
+        Variable::Void => vec![],
+        Variable::Array(bytes) => as_bytes(Variable::Array(bytes))?,
+        any => any.to_string().into_bytes(),
+    };
+
+    if !res.body.is_empty() && !res.headers.iter().any(|(name, _)| name == "content-type") {
+        res.headers.push(("content-type".to_string(), "text/html; charset=utf-8".to_string()));
+    }
+
+    Ok(res)
+}
+
+/// Finds the route for a request and runs its handler. Only fails
+/// if the handler called `exit`, which stops the server.
+fn dispatch(routes: &[Route], req: RawRequest, scope: &mut Scope) -> anyhow::Result<Response> {
+    let (path, query) = req.target.split_once('?').unwrap_or((&req.target, ""));
+
+    // As with `encoding.url_decode`, anything that
+    // doesn't decode to UTF-8 is turned away.
+    let (decoded, query) = match (url_decode(path), parse_query(query)) {
+        (Ok(decoded), Ok(query)) => (decoded, query),
+        (Err(err), _) | (_, Err(err)) => return Ok(Response::text(400, format!("{:#}", err))),
+    };
+
+    let mut path_matched = false;
+
+    for route in routes {
+        let params = match match_path(&route.pattern, path) {
+            Some(params) => params,
+            None => continue,
+        };
+        path_matched = true;
+
+        if route.method != "*" && route.method != req.method {
+            continue;
+        }
+
+        let mut request = BTreeMap::new();
+        request.insert("method".to_string(), Variable::Str(req.method.clone()));
+        request.insert("path".to_string(), Variable::Str(decoded.clone()));
+        request.insert("params".to_string(), Variable::Map(params));
+        request.insert("query".to_string(), Variable::Map(query.clone()));
+        request.insert(
+            "headers".to_string(),
+            Variable::Map(
+                req.headers
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Variable::Str(value.clone())))
+                    .collect(),
+            ),
+        );
+        request.insert(
+            "body".to_string(),
+            Variable::Str(String::from_utf8_lossy(&req.body).into_owned()),
+        );
+
+        return match interpreter::run(
+            Some(route.handler.clone()),
+            scope,
+            vec![Variable::Map(request)],
+            None,
+        )
+        .and_then(into_response)
+        {
+            Err(err) if Err::exit_code(&err).is_some() => Err(err),
+            Err(err) => Ok(Response::text(500, format!("{:#}", err))),
+            Ok(res) => Ok(res),
+        };
+    }
+
+    if path_matched {
+        Ok(Response::text(405, "Method Not Allowed"))
+    } else {
+        Ok(Response::text(404, "Not Found"))
+    }
+}
+
+fn listen(handle: Handle, addr: String, max: Option<usize>, scope: &mut Scope) -> anyhow::Result<()> {
+    // Copied out, so the server isn't locked while handlers run.
+    let routes = handle.with("server", |server: &mut Server| Ok(server.routes.clone()))?;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    let listener = runtime
+        .block_on(TcpListener::bind(&addr))
+        .with_context(|| format!("Failed to listen on `{}`", addr))?;
+
+    writeln!(scope.stdout, "Listening on http://{}", listener.local_addr()?)?;
+    scope.stdout.flush()?;
+
+    let mut handled = 0;
+
+    while max.is_none_or(|max| handled < max) {
+        // Failing to accept a connection (it was reset already, or the
+        // process is out of file descriptors) doesn't stop the server.
+        let mut stream = match runtime.block_on(listener.accept()) {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                writeln!(scope.stdout, "Failed to accept a connection: {}", err)?;
+                scope.stdout.flush()?;
+                // So that an error that keeps happening doesn't spin.
+                std::thread::sleep(ACCEPT_RETRY);
+                continue;
+            }
+        };
+        let req = runtime.block_on(async {
+            tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await
+        });
+
+        // Handlers run outside of the runtime, so that they can
+        // use blocking libraries (like `internet`) themselves.
+        let res = match req {
+            Ok(Ok(Some(req))) => dispatch(&routes, req, scope)?,
+            // Connected, and left without asking for anything.
+            Ok(Ok(None)) => continue,
+            Ok(Err(err)) => Response::text(400, format!("{:#}", err)),
+            Err(_) => Response::text(408, "Request Timeout"),
+        };
+
+        // A client that hung up early is its own problem.
+        let _ = runtime.block_on(write_response(&mut stream, res));
+
+        handled += 1;
+    }
+
+    Ok(())
+}
+
+fn as_server(var: Variable) -> anyhow::Result<Handle> {
+    match var {
+        Variable::Handle(handle) if handle.kind == "server" => Ok(handle),
+        Variable::Handle(handle) => bail!(Err::HandleMismatch("server", handle.kind)),
+        any => bail!(Err::VarTypeMismatch(Variable::Str("server handle".to_string()), any)),
+    }
+}
+
+/// Provides an HTTP server, whose routes are handled by Shrimp functions.
+pub fn server() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        "new" => |args, _| {
+            expect_args(&args, 0, 0)?;
+
+            Ok(Variable::Handle(Handle::new("server", Server::default())))
+        }
+        // route(server method pattern handler)
+        // method is e.g. "GET", or "*" for any method. pattern is a path,
+        // where `:name` matches any one segment and a final `*` matches
+        // the rest. The first route that fits a request handles it.
+        "route" => |args, _| {
+            expect_args(&args, 4, 4)?;
+            let mut args = args.into_iter();
+            let handle = as_server(args.next().unwrap())?;
+            let method = as_str(args.next().unwrap())?.to_uppercase();
+            let pattern = parse_pattern(&as_str(args.next().unwrap())?)?;
+
+            let handler = match args.next().unwrap() {
+                func @ (Variable::Function(_) | Variable::NativeFunction(_)) => func,
+                any => bail!(Err::VarTypeMismatch(
+                    Variable::Str("function".to_string()),
+                    any
+                )),
+            };
+
+            handle.with("server", |server: &mut Server| {
+                server.routes.push(Route {
+                    method,
+                    pattern,
+                    handler,
+                });
+                Ok(Variable::Void)
+            })
+        }
+        // listen(server port max?)
+        // Serves requests on localhost until the program is stopped,
+        // or until `max` requests have been handled. port can also be
+        // a full address, like "0.0.0.0:8080".
+        "listen" => |args, scope| {
+            expect_args(&args, 2, 3)?;
+            let mut args = args.into_iter();
+            let handle = as_server(args.next().unwrap())?;
+
+            let addr = match args.next().unwrap() {
+                Variable::Num(port) => format!("127.0.0.1:{}", port),
+                any => as_str(any)?,
+            };
+
+            let max = match args.next() {
+                Some(Variable::Num(max)) if max >= 0.0 => Some(max as usize),
+                Some(any) => bail!(Err::VarTypeMismatch(Variable::Num(0.0), any)),
+                None => None,
+            };
+
+            listen(handle, addr, max, scope)?;
+            Ok(Variable::Void)
+        }
+    };
+
+    map
+}
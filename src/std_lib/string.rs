@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+
+use super::{as_array, as_int, as_str, expect_args};
+
+/// Turns a byte offset (what `str::find` returns) into a
+/// character index, which is what Shrimp scripts count in.
+fn char_index(string: &str, byte: usize) -> usize {
+    string[..byte].chars().count()
+}
+
+/// A character count, from a whole, non-negative number.
+fn as_count(var: Variable) -> anyhow::Result<usize> {
+    match as_int(var)? {
+        num if num >= 0 => Ok(num as usize),
+        num => bail!(Err::NotAnInteger(Variable::Num(num as f64))),
+    }
+}
+
+fn as_char(var: Variable) -> anyhow::Result<char> {
+    let string = as_str(var)?;
+    let mut chars = string.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(char), None) => Ok(char),
+        _ => bail!(Err::NotAChar(string)),
+    }
+}
+
+/// Makes room for `bytes` bytes repeated `times` times. Running
+/// out of memory is an error, rather than aborting the interpreter.
+fn repeat_buffer(bytes: usize, times: usize) -> anyhow::Result<String> {
+    let mut out = String::new();
+
+    match bytes.checked_mul(times) {
+        Some(len) if out.try_reserve_exact(len).is_ok() => Ok(out),
+        _ => bail!(Err::RepeatTooLong(bytes, times)),
+    }
+}
+
+/// replace(string from to count?): replaces every `from`, or
+/// only the first `count` of them. Shared with the global `replace`.
+pub(crate) fn replace(args: Vec<Variable>) -> anyhow::Result<Variable> {
+    expect_args(&args, 3, 4)?;
+    let mut args = args.into_iter();
+    let string = as_str(args.next().unwrap())?;
+    let from = as_str(args.next().unwrap())?;
+    let to = as_str(args.next().unwrap())?;
+
+    Ok(Variable::Str(match args.next() {
+        Some(count) => string.replacen(&from, &to, as_count(count)?),
+        None => string.replace(&from, &to),
+    }))
+}
+
+/// Splits text on `\n` or `\r\n`. Shared with the global `lines`.
+pub(crate) fn lines(string: &str) -> Variable {
+    Variable::Array(string.lines().map(|x| Variable::Str(x.to_string())).collect())
+}
+
+/// pad(string width fill?) on either side.
+fn pad(args: Vec<Variable>, left: bool) -> anyhow::Result<Variable> {
+    expect_args(&args, 2, 3)?;
+    let mut args = args.into_iter();
+    let string = as_str(args.next().unwrap())?;
+    let width = as_count(args.next().unwrap())?;
+    let fill = match args.next() {
+        Some(fill) => as_char(fill)?,
+        None => ' ',
+    };
+
+    let count = width.saturating_sub(string.chars().count());
+    let mut padding = repeat_buffer(fill.len_utf8(), count)?;
+    padding.extend(std::iter::repeat_n(fill, count));
+
+    Ok(Variable::Str(if left {
+        padding + &string
+    } else {
+        string + &padding
+    }))
+}
+
+/// Provides string manipulation. Indexes and lengths are in
+/// characters, not bytes.
+pub fn string() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    /// Functions that take one string and return another.
+    macro_rules! unary_fn {
+        (
+				$(
+					$name: expr => $func: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native(|args, _| {
+                expect_args(&args, 1, 1)?;
+                let func: fn(&str) -> String = $func;
+
+                Ok(Variable::Str(func(&as_str(args.into_iter().next().unwrap())?)))
+            })); )*
+        };
+    }
+
+    unary_fn! {
+        "upper" => str::to_uppercase
+        "lower" => str::to_lowercase
+        "trim" => |x| x.trim().to_string()
+        "trim_start" => |x| x.trim_start().to_string()
+        "trim_end" => |x| x.trim_end().to_string()
+    }
+
+    /// Functions that check a string against a pattern.
+    macro_rules! search_fn {
+        (
+				$(
+					$name: expr => $func: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native(|args, _| {
+                expect_args(&args, 2, 2)?;
+                let mut args = args.into_iter();
+                let string = as_str(args.next().unwrap())?;
+                let pattern = as_str(args.next().unwrap())?;
+                let func: fn(&str, &str) -> Variable = $func;
+
+                Ok(func(&string, &pattern))
+            })); )*
+        };
+    }
+
+    search_fn! {
+        "contains" => |x, pat| Variable::Bool(x.contains(pat))
+        "starts_with" => |x, pat| Variable::Bool(x.starts_with(pat))
+        "ends_with" => |x, pat| Variable::Bool(x.ends_with(pat))
+        // The index of the first match, or void.
+        "find" => |x, pat| match x.find(pat) {
+            Some(byte) => Variable::Num(char_index(x, byte) as f64),
+            None => Variable::Void,
+        }
+        // The index of the last match, or void.
+        "rfind" => |x, pat| match x.rfind(pat) {
+            Some(byte) => Variable::Num(char_index(x, byte) as f64),
+            None => Variable::Void,
+        }
+    }
+
+    insert_fn! {
+        "replace" => |args, _| replace(args)
+        // slice(string start end?) -> the characters from `start` up to
+        // (but not including) `end`, or to the end of the string.
+        "slice" => |args, _| {
+            expect_args(&args, 2, 3)?;
+            let mut args = args.into_iter();
+            let string = as_str(args.next().unwrap())?;
+            let len = string.chars().count();
+            let start = as_count(args.next().unwrap())?;
+            let end = match args.next() {
+                Some(end) => as_count(end)?,
+                None => len,
+            };
+
+            if end > len {
+                bail!(Err::OutOfBoundsIndex(len, end))
+            }
+            if start > end {
+                bail!(Err::OutOfBoundsIndex(end, start))
+            }
+
+            Ok(Variable::Str(string.chars().skip(start).take(end - start).collect()))
+        }
+        "repeat" => |args, _| {
+            expect_args(&args, 2, 2)?;
+            let mut args = args.into_iter();
+            let string = as_str(args.next().unwrap())?;
+            let times = as_count(args.next().unwrap())?;
+
+            let mut out = repeat_buffer(string.len(), times)?;
+            // Repeating nothing a huge number of times would still loop.
+            if !string.is_empty() {
+                for _ in 0..times {
+                    out.push_str(&string);
+                }
+            }
+
+            Ok(Variable::Str(out))
+        }
+        // pad_left(string width fill?) -> pads with `fill` (a space by
+        // default) until the string is `width` characters long.
+        "pad_left" => |args, _| pad(args, true)
+        "pad_right" => |args, _| pad(args, false)
+        // join(arr separator?) -> every element, as they would be printed,
+        // one after the other.
+        "join" => |args, _| {
+            expect_args(&args, 1, 2)?;
+            let mut args = args.into_iter();
+            let arr = as_array(args.next().unwrap())?;
+            let separator = match args.next() {
+                Some(separator) => as_str(separator)?,
+                None => String::new(),
+            };
+
+            Ok(Variable::Str(
+                arr.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(&separator)
+            ))
+        }
+        "lines" => |args, _| {
+            expect_args(&args, 1, 1)?;
+
+            Ok(lines(&as_str(args.into_iter().next().unwrap())?))
+        }
+    };
+
+    map
+}
@@ -0,0 +1,549 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+use anyhow::Context;
+
+use super::html::escape;
+use super::{as_map, as_str, expect_args};
+
+/// Where in a template something is, for error messages.
+#[derive(Clone)]
+struct Location {
+    file: String,
+    line: usize,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, line {}", self.file, self.line)
+    }
+}
+
+fn error(at: &Location, message: impl Into<String>) -> Err {
+    Err::BadTemplate(at.to_string(), message.into())
+}
+
+enum Token {
+    Text(String),
+    /// `{{ ... }}`
+    Value(String, Location),
+    /// `{% ... %}`
+    Tag(String, Location),
+}
+
+/// A variable, like `user.name` or `items.0`.
+type VarPath = Vec<String>;
+
+enum Piece {
+    Text(String),
+    Value(VarPath, Location),
+    /// `for item in items`, or `for key, value in map`.
+    For {
+        item: String,
+        value: Option<String>,
+        over: VarPath,
+        body: Vec<Piece>,
+        at: Location,
+    },
+    /// Each branch runs if its variable is (or with `not`, isn't) truthy.
+    If {
+        branches: Vec<(bool, VarPath, Vec<Piece>)>,
+        otherwise: Vec<Piece>,
+    },
+}
+
+/// A parsed template, as handed out by `template.load` and `template.parse`.
+struct Template(Vec<Piece>);
+
+/// Splits a template into text and tags. `{# ... #}` comments are dropped.
+fn tokenize(source: &str, file: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut text = String::new();
+    let mut rest = source;
+    let mut line = 1;
+    // Whether `text` starts at the start of a line.
+    let mut line_start = true;
+
+    loop {
+        let start = match ["{{", "{%", "{#"].iter().filter_map(|x| rest.find(x)).min() {
+            Some(start) => start,
+            None => {
+                text += rest;
+                break;
+            }
+        };
+
+        text += &rest[..start];
+        line += rest[..start].matches('\n').count();
+
+        let at = Location {
+            file: file.to_string(),
+            line,
+        };
+
+        let open = &rest[start..start + 2];
+        let close = match open {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+
+        let end = match rest[start + 2..].find(close) {
+            Some(end) => start + 2 + end,
+            None => bail!(error(&at, format!("`{}` is never closed", open))),
+        };
+
+        let inner = rest[start + 2..end].to_string();
+        line += inner.matches('\n').count();
+        rest = &rest[end + 2..];
+
+        if open == "{{" {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+            tokens.push(Token::Value(inner.trim().to_string(), at));
+            line_start = false;
+            continue;
+        }
+
+        // A tag or comment on a line of its own takes the whole line
+        // with it, so that they don't leave blank lines in the output.
+        let before = text.rfind('\n').map(|x| x + 1);
+        let alone_before =
+            text[before.unwrap_or(0)..].trim().is_empty() && (before.is_some() || line_start);
+        let after = rest.find('\n').unwrap_or(rest.len());
+        let alone_after = rest[..after].trim().is_empty();
+
+        if alone_before && alone_after {
+            text.truncate(before.unwrap_or(0));
+            // The last line of a template may not end with a newline.
+            if after < rest.len() {
+                rest = &rest[after + 1..];
+                line += 1;
+            } else {
+                rest = "";
+            }
+            line_start = true;
+        } else {
+            line_start = false;
+        }
+
+        tokens.push(Token::Text(std::mem::take(&mut text)));
+
+        if open == "{%" {
+            tokens.push(Token::Tag(inner.trim().to_string(), at));
+        }
+    }
+
+    tokens.push(Token::Text(text));
+
+    Ok(tokens
+        .into_iter()
+        .filter(|x| !matches!(x, Token::Text(text) if text.is_empty()))
+        .collect())
+}
+
+fn var_path(expr: &str, at: &Location) -> anyhow::Result<VarPath> {
+    let path: VarPath = expr.split('.').map(str::to_string).collect();
+
+    let valid = path
+        .iter()
+        .all(|x| !x.is_empty() && x.chars().all(|c| c.is_alphanumeric() || c == '_'));
+
+    if !valid {
+        bail!(error(at, format!("`{}` is not a variable", expr)))
+    }
+
+    Ok(path)
+}
+
+/// The tag that ended a body: its keyword, the rest of it, and where it was.
+type EndTag = (String, String, Location);
+
+/// Reads templates, following includes.
+struct Parser<'a> {
+    tokens: std::vec::IntoIter<Token>,
+    /// Where includes are looked up.
+    dir: &'a Path,
+    /// Files that are part-way through being included, to catch cycles.
+    loading: &'a mut Vec<PathBuf>,
+}
+
+impl Parser<'_> {
+    /// Parses until one of the `ends` tags, which is handed back
+    /// along with the rest of it. `None` if the template ran out first.
+    fn body(&mut self, ends: &[&str]) -> anyhow::Result<(Vec<Piece>, Option<EndTag>)> {
+        let mut pieces = vec![];
+
+        while let Some(token) = self.tokens.next() {
+            let (tag, at) = match token {
+                Token::Text(text) => {
+                    pieces.push(Piece::Text(text));
+                    continue;
+                }
+                Token::Value(expr, at) => {
+                    pieces.push(Piece::Value(var_path(&expr, &at)?, at));
+                    continue;
+                }
+                Token::Tag(tag, at) => (tag, at),
+            };
+
+            let (keyword, rest) = match tag.split_once(char::is_whitespace) {
+                Some((keyword, rest)) => (keyword.to_string(), rest.trim().to_string()),
+                None => (tag, String::new()),
+            };
+
+            if ends.contains(&keyword.as_str()) {
+                return Ok((pieces, Some((keyword, rest, at))));
+            }
+
+            match keyword.as_str() {
+                "for" => pieces.push(self.for_loop(&rest, at)?),
+                "if" => pieces.push(self.if_chain(&rest, at)?),
+                "include" => pieces.extend(self.include(&rest, at)?),
+                "endfor" | "endif" | "elif" | "else" => {
+                    bail!(error(&at, format!("Unexpected `{}`", keyword)))
+                }
+                any => bail!(error(&at, format!("Unknown tag `{}`", any))),
+            }
+        }
+
+        Ok((pieces, None))
+    }
+
+    fn for_loop(&mut self, rest: &str, at: Location) -> anyhow::Result<Piece> {
+        let (names, over) = match rest.split_once(" in ") {
+            Some(split) => split,
+            None => bail!(error(&at, "Expected `for item in items`")),
+        };
+
+        let mut names = names.split(',').map(|x| x.trim().to_string());
+        let item = names.next().unwrap_or_default();
+        let value = names.next();
+
+        let valid =
+            |x: &String| !x.is_empty() && x.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if !valid(&item) || !value.iter().all(valid) || names.next().is_some() {
+            bail!(error(
+                &at,
+                "Expected `for item in items` or `for key, value in map`"
+            ))
+        }
+
+        let over = var_path(over.trim(), &at)?;
+
+        let (body, end) = self.body(&["endfor"])?;
+        if end.is_none() {
+            bail!(error(&at, "`for` is never closed (expected `endfor`)"))
+        }
+
+        Ok(Piece::For {
+            item,
+            value,
+            over,
+            body,
+            at,
+        })
+    }
+
+    fn if_chain(&mut self, rest: &str, at: Location) -> anyhow::Result<Piece> {
+        let condition = |rest: &str, at: &Location| -> anyhow::Result<(bool, VarPath)> {
+            match rest.strip_prefix("not ") {
+                Some(rest) => Ok((true, var_path(rest.trim(), at)?)),
+                None => Ok((false, var_path(rest, at)?)),
+            }
+        };
+
+        let mut branches = vec![];
+        let (mut negate, mut path) = condition(rest, &at)?;
+
+        loop {
+            let (body, end) = self.body(&["elif", "else", "endif"])?;
+            branches.push((negate, path, body));
+
+            match end {
+                Some((keyword, rest, at)) if keyword == "elif" => {
+                    let next = condition(&rest, &at)?;
+                    negate = next.0;
+                    path = next.1;
+                }
+                Some((keyword, _, _)) if keyword == "else" => {
+                    let (otherwise, end) = self.body(&["endif"])?;
+                    if end.is_none() {
+                        bail!(error(&at, "`if` is never closed (expected `endif`)"))
+                    }
+                    return Ok(Piece::If {
+                        branches,
+                        otherwise,
+                    });
+                }
+                Some(_) => {
+                    return Ok(Piece::If {
+                        branches,
+                        otherwise: vec![],
+                    })
+                }
+                None => bail!(error(&at, "`if` is never closed (expected `endif`)")),
+            }
+        }
+    }
+
+    /// `include "partial.html"`, looked up next to the including template.
+    fn include(&mut self, rest: &str, at: Location) -> anyhow::Result<Vec<Piece>> {
+        let name = match rest.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+            Some(name) => name,
+            None => bail!(error(&at, "Expected `include \"file\"`")),
+        };
+
+        let path = self.dir.join(name);
+
+        let canonical = path
+            .canonicalize()
+            .map_err(|_| error(&at, format!("Can't find `{}`", path.display())))?;
+
+        if self.loading.contains(&canonical) {
+            bail!(error(&at, format!("`{}` includes itself", name)))
+        }
+
+        load(&path, self.loading).with_context(|| format!("Included from {}", at))
+    }
+}
+
+fn parse(
+    source: &str,
+    file: &str,
+    dir: &Path,
+    loading: &mut Vec<PathBuf>,
+) -> anyhow::Result<Vec<Piece>> {
+    let mut parser = Parser {
+        tokens: tokenize(source, file)?.into_iter(),
+        dir,
+        loading,
+    };
+
+    Ok(parser.body(&[])?.0)
+}
+
+fn load(path: &Path, loading: &mut Vec<PathBuf>) -> anyhow::Result<Vec<Piece>> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to open {}. (Does it exist?)", path.display()))?;
+
+    loading.push(path.canonicalize()?);
+
+    let pieces = parse(
+        &source,
+        &path.display().to_string(),
+        path.parent().unwrap_or_else(|| Path::new("")),
+        loading,
+    );
+
+    loading.pop();
+
+    pieces
+}
+
+/// Whether `if` takes a value as yes. Empty values and zero don't count.
+fn truthy(var: Option<&Variable>) -> bool {
+    match var {
+        None | Some(Variable::Void) => false,
+        Some(Variable::Bool(b)) => *b,
+        Some(Variable::Num(num)) => *num != 0.0,
+        Some(Variable::Str(string)) | Some(Variable::Html(string)) => !string.is_empty(),
+        Some(Variable::Array(arr)) => !arr.is_empty(),
+        Some(Variable::Map(map)) => !map.is_empty(),
+        Some(_) => true,
+    }
+}
+
+struct Renderer<'a> {
+    context: &'a BTreeMap<String, Variable>,
+    /// Loop variables, innermost last.
+    locals: Vec<(String, Variable)>,
+    /// Whether values are escaped as HTML.
+    escape: bool,
+    out: String,
+}
+
+impl Renderer<'_> {
+    fn lookup(&self, path: &[String]) -> Option<&Variable> {
+        let mut current = match self.locals.iter().rev().find(|(name, _)| *name == path[0]) {
+            Some((_, var)) => var,
+            None => self.context.get(&path[0])?,
+        };
+
+        for key in &path[1..] {
+            current = match current {
+                Variable::Map(map) => map.get(key)?,
+                Variable::Array(arr) => arr.get(key.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+
+    fn render(&mut self, pieces: &[Piece]) -> anyhow::Result<()> {
+        for piece in pieces {
+            match piece {
+                Piece::Text(text) => self.out += text,
+                Piece::Value(path, at) => match self.lookup(path) {
+                    Some(Variable::Html(html)) => self.out += &html.clone(),
+                    Some(Variable::Void) => {}
+                    Some(var) if self.escape => self.out += &escape(&var.to_string()),
+                    Some(var) => self.out += &var.to_string(),
+                    None => bail!(error(at, format!("`{}` is not defined", path.join(".")))),
+                },
+                Piece::For {
+                    item,
+                    value,
+                    over,
+                    body,
+                    at,
+                } => {
+                    let items: Vec<(Variable, Variable)> = match self.lookup(over) {
+                        Some(Variable::Array(arr)) => arr
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, x)| (Variable::Num(idx as f64), x.clone()))
+                            .collect(),
+                        Some(Variable::Map(map)) => map
+                            .iter()
+                            .map(|(key, x)| (Variable::Str(key.clone()), x.clone()))
+                            .collect(),
+                        Some(any) => {
+                            bail!(error(at, format!("Can't loop over {}", any.as_words())))
+                        }
+                        None => bail!(error(at, format!("`{}` is not defined", over.join(".")))),
+                    };
+
+                    let len = items.len();
+
+                    for (idx, (key, var)) in items.into_iter().enumerate() {
+                        let mut info = BTreeMap::new();
+                        info.insert("index".to_string(), Variable::Num(idx as f64));
+                        info.insert("first".to_string(), Variable::Bool(idx == 0));
+                        info.insert("last".to_string(), Variable::Bool(idx + 1 == len));
+
+                        self.locals.push(("loop".to_string(), Variable::Map(info)));
+                        match value {
+                            // A single name gets the items of arrays, and the keys of maps.
+                            None if matches!(key, Variable::Str(_)) => {
+                                self.locals.push((item.clone(), key))
+                            }
+                            None => self.locals.push((item.clone(), var)),
+                            Some(value) => {
+                                self.locals.push((item.clone(), key));
+                                self.locals.push((value.clone(), var));
+                            }
+                        }
+
+                        let res = self.render(body);
+                        self.locals
+                            .truncate(self.locals.len() - if value.is_some() { 3 } else { 2 });
+                        res?;
+                    }
+                }
+                Piece::If {
+                    branches,
+                    otherwise,
+                } => {
+                    let branch = branches
+                        .iter()
+                        .find(|(negate, path, _)| truthy(self.lookup(path)) != *negate)
+                        .map(|(_, _, body)| body);
+
+                    self.render(branch.unwrap_or(otherwise))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn render(
+    pieces: &[Piece],
+    context: &BTreeMap<String, Variable>,
+    escape: bool,
+) -> anyhow::Result<String> {
+    let mut renderer = Renderer {
+        context,
+        locals: vec![],
+        escape,
+        out: String::new(),
+    };
+
+    renderer.render(pieces)?;
+
+    Ok(renderer.out)
+}
+
+/// render(template context?), where template is a handle or the source of one.
+fn render_args(args: Vec<Variable>, escape: bool) -> anyhow::Result<String> {
+    expect_args(&args, 1, 2)?;
+    let mut args = args.into_iter();
+    let template = args.next().unwrap();
+
+    let context = match args.next() {
+        Some(context) => as_map(context)?,
+        None => BTreeMap::new(),
+    };
+
+    match template {
+        Variable::Handle(handle) => handle.with("template", |template: &mut Template| {
+            render(&template.0, &context, escape)
+        }),
+        any => {
+            let pieces = parse(&as_str(any)?, "template", Path::new(""), &mut vec![])?;
+            render(&pieces, &context, escape)
+        }
+    }
+}
+
+/// Fills in templates with Shrimp values.
+pub fn template() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        // load(path), reading the template (and anything it includes) now.
+        "load" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let path = as_str(args.into_iter().next().unwrap())?;
+
+            let pieces = load(Path::new(&path), &mut vec![])?;
+
+            Ok(Variable::Handle(Handle::new("template", Template(pieces))))
+        }
+        // parse(source), for templates that aren't in files. Includes
+        // are looked up from the working directory.
+        "parse" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let source = as_str(args.into_iter().next().unwrap())?;
+
+            let pieces = parse(&source, "template", Path::new(""), &mut vec![])?;
+
+            Ok(Variable::Handle(Handle::new("template", Template(pieces))))
+        }
+        "render" => |args, _| {
+            Ok(Variable::Html(render_args(args, true)?))
+        }
+        // Like render, but for text that isn't HTML, so nothing is escaped.
+        "render_text" => |args, _| {
+            Ok(Variable::Str(render_args(args, false)?))
+        }
+    }
+
+    map
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Instant;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+
+use crate::data_types::*;
+use crate::errors::Err;
+
+use anyhow::bail;
+use anyhow::Context;
+
+use super::{as_duration, as_num, as_str, expect_args};
+
+/// What `monotonic` counts from.
+static START: Lazy<Instant> = Lazy::new(Instant::now);
+
+fn from_millis<Tz: TimeZone>(tz: Tz, ms: f64) -> anyhow::Result<DateTime<Tz>> {
+    match tz.timestamp_millis_opt(ms as i64).single() {
+        Some(time) => Ok(time),
+        None => bail!(Err::TimestampOutOfRange(ms)),
+    }
+}
+
+fn format<Tz: TimeZone>(tz: Tz, args: Vec<Variable>) -> anyhow::Result<Variable>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    expect_args(&args, 2, 2)?;
+    let mut args = args.into_iter();
+    let ms = as_num(args.next().unwrap())?;
+    let pattern = as_str(args.next().unwrap())?;
+
+    // A bad pattern only shows up while formatting.
+    let mut out = String::new();
+    if write!(out, "{}", from_millis(tz, ms)?.format(&pattern)).is_err() {
+        bail!(Err::InvalidTimeFormat(pattern))
+    }
+
+    Ok(Variable::Str(out))
+}
+
+/// Patterns without a time zone are read in `tz`, and
+/// patterns without a time are read as midnight.
+fn parse<Tz: TimeZone>(tz: Tz, args: Vec<Variable>) -> anyhow::Result<Variable> {
+    expect_args(&args, 2, 2)?;
+    let mut args = args.into_iter();
+    let text = as_str(args.next().unwrap())?;
+    let pattern = as_str(args.next().unwrap())?;
+
+    if let Ok(time) = DateTime::parse_from_str(&text, &pattern) {
+        return Ok(Variable::Num(time.timestamp_millis() as f64));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(&text, &pattern)
+        .or_else(|err| {
+            NaiveDate::parse_from_str(&text, &pattern)
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
+                .map_err(|_| err)
+        })
+        .with_context(|| format!("Failed to parse `{}` with the pattern `{}`", text, pattern))?;
+
+    match tz.from_local_datetime(&naive).earliest() {
+        Some(time) => Ok(Variable::Num(time.timestamp_millis() as f64)),
+        None => bail!(Err::InvalidTime(text)),
+    }
+}
+
+/// Provides the current time, sleeping, and formatting and parsing dates.
+/// Timestamps are milliseconds since the Unix epoch, and durations are
+/// milliseconds too, so they can be combined with `add` and `sub`.
+pub fn time() -> BTreeMap<String, Variable> {
+    let mut map = BTreeMap::new();
+
+    macro_rules! insert_fn {
+        (
+				$(
+					$name: expr => $val: expr
+				)*
+			) => {
+            $( map.insert($name.to_string(), Variable::native($val)); )*
+        };
+    }
+
+    insert_fn! {
+        "now" => |args, _| {
+            expect_args(&args, 0, 0)?;
+
+            Ok(Variable::Num(Utc::now().timestamp_millis() as f64))
+        }
+        // Milliseconds since an arbitrary point. Never goes backwards,
+        // unlike `now`, so it's the one to use for measuring.
+        "monotonic" => |args, _| {
+            expect_args(&args, 0, 0)?;
+
+            Ok(Variable::Num(START.elapsed().as_secs_f64() * 1000.0))
+        }
+        // elapsed(start) -> milliseconds since `start`, a `monotonic` time.
+        "elapsed" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let start = as_num(args.into_iter().next().unwrap())?;
+
+            Ok(Variable::Num(START.elapsed().as_secs_f64() * 1000.0 - start))
+        }
+        "sleep" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            let duration = as_duration(args.into_iter().next().unwrap())?;
+
+            std::thread::sleep(duration);
+            Ok(Variable::Void)
+        }
+        // format(timestamp pattern), in local time.
+        "format" => |args, _| format(Local, args)
+        "format_utc" => |args, _| format(Utc, args)
+        // parse(text pattern) -> timestamp
+        "parse" => |args, _| parse(Local, args)
+        "parse_utc" => |args, _| parse(Utc, args)
+        // Durations, in milliseconds.
+        "seconds" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            Ok(Variable::Num(as_num(args.into_iter().next().unwrap())? * 1000.0))
+        }
+        "minutes" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            Ok(Variable::Num(as_num(args.into_iter().next().unwrap())? * 60_000.0))
+        }
+        "hours" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            Ok(Variable::Num(as_num(args.into_iter().next().unwrap())? * 3_600_000.0))
+        }
+        "days" => |args, _| {
+            expect_args(&args, 1, 1)?;
+            Ok(Variable::Num(as_num(args.into_iter().next().unwrap())? * 86_400_000.0))
+        }
+    };
+
+    map
+}
@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+
+use crate::interpreter::Interpreter;
+use crate::modules;
+use crate::nodes::Node;
+use crate::scope::SharedBuffer;
+
+/// The outcome of a single `@test_...()` function.
+pub struct TestResult {
+    pub file: String,
+    pub name: String,
+    pub duration: Duration,
+    /// `None` if the test passed, otherwise why it failed.
+    pub failure: Option<String>,
+    /// Everything the test printed while it ran.
+    pub output: String,
+}
+
+/// Finds every zero-argument `@test_...()` function declared in `file`.
+/// Tests in the files it `use`s belong to those files, so they're left out.
+pub fn discover(file: impl AsRef<Path>) -> anyhow::Result<Vec<String>> {
+    let file = file.as_ref();
+    let source = File::open(file)
+        .with_context(|| format!("Failed to open {}. (Does it exist?)", file.display()))?;
+
+    let nodes = modules::parse_source(source)
+        .with_context(|| format!("Error originated in {}", file.display()))?;
+
+    let mut tests: Vec<String> = nodes
+        .into_iter()
+        .filter_map(|node| match node {
+            Node::FunctionDecl { name, args, .. }
+                if name.starts_with("test_") && args.is_empty() =>
+            {
+                Some(name)
+            }
+            _ => None,
+        })
+        .collect();
+
+    tests.sort();
+    Ok(tests)
+}
+
+/// Runs one test with a freshly loaded interpreter, so that
+/// no variables leak in from other tests.
+pub fn run_test(file: impl AsRef<Path>, name: &str) -> TestResult {
+    let file = file.as_ref();
+    let output = SharedBuffer::new();
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stdout(output.clone());
+
+    let start = Instant::now();
+
+    // A panicking native fails the test, rather than the whole run.
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        interpreter
+            .load_file(file)
+            .and_then(|_| interpreter.call(name, vec![]))
+    }));
+
+    let failure = match res {
+        Ok(Ok(_)) => None,
+        Ok(Err(err)) => Some(format!("{:#}", err)),
+        Err(payload) => Some(format!("Panicked: {}", panic_message(&*payload))),
+    };
+
+    TestResult {
+        file: file.display().to_string(),
+        name: name.to_string(),
+        duration: start.elapsed(),
+        failure,
+        output: output.contents(),
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "(no message)".to_string(),
+        },
+    }
+}
+
+/// Runs every test in every file, reporting to `out` as it goes.
+/// Returns `true` only if every test passed.
+pub fn run_files(files: &[String], out: &mut impl Write) -> anyhow::Result<bool> {
+    let mut results = vec![];
+
+    for file in files {
+        let tests = match discover(file) {
+            Ok(tests) => tests,
+            // Reported like a failed test, so the other files still run.
+            Err(err) => {
+                writeln!(out, "\nfailed to load {}", file)?;
+
+                results.push(TestResult {
+                    file: file.clone(),
+                    name: "(loading the file)".to_string(),
+                    duration: Duration::ZERO,
+                    failure: Some(format!("{:#}", err)),
+                    output: String::new(),
+                });
+                continue;
+            }
+        };
+
+        writeln!(out, "\nrunning {} tests from {}", tests.len(), file)?;
+
+        for name in tests {
+            let res = run_test(file, &name);
+
+            writeln!(
+                out,
+                "test {} ... {} ({:.2?})",
+                res.name,
+                match res.failure {
+                    Some(_) => "FAILED",
+                    None => "ok",
+                },
+                res.duration
+            )?;
+
+            results.push(res);
+        }
+    }
+
+    let failures: Vec<&TestResult> = results.iter().filter(|x| x.failure.is_some()).collect();
+
+    if !failures.is_empty() {
+        writeln!(out, "\nfailures:")?;
+
+        for res in &failures {
+            writeln!(out, "\n---- {} ({}) ----", res.name, res.file)?;
+            if !res.output.is_empty() {
+                write!(out, "{}", res.output)?;
+                if !res.output.ends_with('\n') {
+                    writeln!(out)?;
+                }
+            }
+            writeln!(out, "{}", res.failure.as_ref().unwrap())?;
+        }
+    }
+
+    writeln!(
+        out,
+        "\ntest result: {}. {} passed; {} failed; finished in {:.2?}",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        results.len() - failures.len(),
+        failures.len(),
+        results.iter().map(|x| x.duration).sum::<Duration>()
+    )?;
+
+    Ok(failures.is_empty())
+}
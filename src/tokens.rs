@@ -7,15 +7,15 @@ use crate::errors::Err;
 
 use crate::panic;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 /// The token enum -- holds all token variants.
 /// - Used in generating the AST.
 pub enum Token {
     /// Any string. 'Hello world!', "Hello world!"
-    /// or					'\'Hello world!\''
+    /// or '\'Hello world!\''
     Str(String),
     /// Any int or float. 123, 1.23, 33.32
-    Num(f32),
+    Num(f64),
     /// Any letters not in a string.
     Ident(String),
     /// Function token `@`.
@@ -43,24 +43,13 @@ pub enum Token {
     ForAssigner,
 }
 
-impl Clone for Token {
-    fn clone(&self) -> Self {
-        match self {
-            Str(string) => Str(string.clone()),
-            Ident(id) => Ident(id.clone()),
-            Num(num) => Num(*num),
-            any => any.clone()
-        }
-    }
-}
-
 impl Token {
-    pub fn as_var(self) -> Variable {
-        match self {
+    pub fn as_var(self) -> anyhow::Result<Variable> {
+        Ok(match self {
             Str(string) => Variable::Str(string),
             Num(num) => Variable::Num(num),
-            any => panic!(Err::UnexpectedToken(Some(any)))
-        }
+            any => anyhow::bail!(Err::UnexpectedToken(Some(any)))
+        })
     }
     pub fn as_words(&self) -> String {
         match self {
@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use config
+@parse(text format) { config.parse(text format) -> out return out }
+@read(path) { config.read(path) -> out return out }
+@stringify(value) { config.stringify(value) -> out return out }
+@round_trip(value) {
+    config.stringify(value) -> text
+    config.parse(text) -> out
+    return out
+}
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn num(n: f64) -> Variable {
+    Variable::Num(n)
+}
+
+fn map(pairs: Vec<(&str, Variable)>) -> Variable {
+    Variable::Map(
+        pairs
+            .into_iter()
+            .map(|(key, x)| (key.to_string(), x))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+fn parse(text: &str, format: &str) -> anyhow::Result<Variable> {
+    shrimp().call("parse", vec![str(text), str(format)])
+}
+
+fn error(result: anyhow::Result<Variable>) -> String {
+    format!("{:#}", result.unwrap_err())
+}
+
+#[test]
+fn parses_toml() {
+    let text = "
+name = \"shrimp\"
+port = 8080
+ratio = 0.5
+debug = true
+released = 2021-03-04
+tags = [\"a\", \"b\"]
+
+[database]
+url = \"postgres://localhost\"
+
+[database.pool]
+size = 4
+";
+
+    assert_eq!(
+        parse(text, "toml").unwrap(),
+        map(vec![
+            ("name", str("shrimp")),
+            ("port", num(8080.0)),
+            ("ratio", num(0.5)),
+            ("debug", Variable::Bool(true)),
+            ("released", str("2021-03-04")),
+            ("tags", Variable::Array(vec![str("a"), str("b")])),
+            (
+                "database",
+                map(vec![
+                    ("url", str("postgres://localhost")),
+                    ("pool", map(vec![("size", num(4.0))])),
+                ])
+            ),
+        ])
+    );
+}
+
+#[test]
+fn parses_ini() {
+    let text = "
+; a comment
+top = level
+[server]
+# another comment
+host = \"example.com\"
+port: 80
+
+[ empty ]
+";
+
+    assert_eq!(
+        parse(text, "INI").unwrap(),
+        map(vec![
+            ("top", str("level")),
+            (
+                "server",
+                map(vec![("host", str("example.com")), ("port", str("80"))])
+            ),
+            ("empty", map(vec![])),
+        ])
+    );
+}
+
+#[test]
+fn errors_say_which_line() {
+    for (text, format, message) in [
+        ("a = 1\nb = 2\nc =\n", "toml", "Invalid TOML (line 3): "),
+        (
+            "a = 1\n[server\n",
+            "ini",
+            "Invalid INI (line 2): Expected `[section]`",
+        ),
+        (
+            "\n\n\njust words",
+            "ini",
+            "Invalid INI (line 4): Expected `key = value`",
+        ),
+        (
+            "a = 1\n[a]\n",
+            "ini",
+            "Invalid INI (line 2): `a` is already a key",
+        ),
+        ("a = 1", "yaml", "Unknown config format `yaml`"),
+    ] {
+        let err = error(parse(text, format));
+        assert!(err.contains(message), "{:?}: {}", text, err);
+    }
+}
+
+#[test]
+fn reads_files_by_extension() {
+    let dir = std::env::temp_dir().join(format!("shrimp-config-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let ini = dir.join("app.ini");
+    fs::write(&ini, "[app]\nname = x\n").unwrap();
+    assert_eq!(
+        shrimp()
+            .call("read", vec![str(&ini.display().to_string())])
+            .unwrap(),
+        map(vec![("app", map(vec![("name", str("x"))]))])
+    );
+
+    // The same text isn't valid TOML, and the file is named in the error.
+    let toml = dir.join("app.toml");
+    fs::write(&toml, "[app]\nname = x\n").unwrap();
+    let toml = toml.display().to_string();
+    let err = error(shrimp().call("read", vec![str(&toml)]));
+    assert!(
+        err.contains(&format!("Invalid TOML ({}, line 2)", toml)),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn stringifies_as_toml() {
+    let value = map(vec![
+        ("a_table", map(vec![("x", num(1.0))])),
+        ("b_plain", str("after a table")),
+        (
+            "list",
+            Variable::Array(vec![num(1.5), Variable::Bool(false)]),
+        ),
+        ("nothing", Variable::Void),
+    ]);
+
+    assert_eq!(
+        shrimp().call("stringify", vec![value.clone()]).unwrap(),
+        str("b_plain = \"after a table\"\nlist = [1.5, false]\n\n[a_table]\nx = 1\n")
+    );
+
+    // Apart from the void, it reads back the same.
+    let mut shrimp = shrimp();
+    let mut expected = match value {
+        Variable::Map(map) => map,
+        any => panic!("expected a map, found {:?}", any),
+    };
+    expected.remove("nothing");
+    let expected = Variable::Map(expected);
+    assert_eq!(
+        shrimp.call("round_trip", vec![expected.clone()]).unwrap(),
+        expected
+    );
+
+    let err = error(shrimp.call("stringify", vec![str("not a map")]));
+    assert!(err.contains("Expected a map, found a string"), "{}", err);
+}
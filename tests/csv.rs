@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use shrimp::{Interpreter, SharedBuffer, Variable};
+
+const SCRIPT: &str = "
+use csv
+@parse(text header delimiter) { csv.parse(text header delimiter) -> out return out }
+@stringify(rows delimiter) { csv.stringify(rows delimiter) -> out return out }
+@names(path) {
+    for csv.rows(path true) => row {
+        println(row.name)
+    }
+}
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn strs(fields: &[&str]) -> Variable {
+    Variable::Array(fields.iter().map(|x| str(x)).collect())
+}
+
+fn map(pairs: &[(&str, &str)]) -> Variable {
+    Variable::Map(
+        pairs
+            .iter()
+            .map(|(key, x)| (key.to_string(), str(x)))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+fn parse(text: &str, header: bool, delimiter: &str) -> anyhow::Result<Variable> {
+    shrimp().call(
+        "parse",
+        vec![str(text), Variable::Bool(header), str(delimiter)],
+    )
+}
+
+fn stringify(rows: Vec<Variable>, delimiter: &str) -> anyhow::Result<Variable> {
+    shrimp().call("stringify", vec![Variable::Array(rows), str(delimiter)])
+}
+
+fn error(result: anyhow::Result<Variable>) -> String {
+    format!("{:#}", result.unwrap_err())
+}
+
+#[test]
+fn reads_and_writes_quoted_fields() {
+    let text = "\"Smith, J\",\"say \"\"hi\"\"\"\r\n\"two\nlines\",plain\n";
+    let rows = vec![
+        strs(&["Smith, J", "say \"hi\""]),
+        strs(&["two\nlines", "plain"]),
+    ];
+
+    assert_eq!(
+        parse(text, false, ",").unwrap(),
+        Variable::Array(rows.clone())
+    );
+    assert_eq!(
+        stringify(rows, ",").unwrap(),
+        str("\"Smith, J\",\"say \"\"hi\"\"\"\n\"two\nlines\",plain\n")
+    );
+}
+
+#[test]
+fn takes_other_delimiters() {
+    assert_eq!(
+        parse("a;b,c\n1;2", false, ";").unwrap(),
+        Variable::Array(vec![strs(&["a", "b,c"]), strs(&["1", "2"])])
+    );
+    assert_eq!(
+        stringify(vec![strs(&["a", "b\tc", "d"])], "\t").unwrap(),
+        str("a\t\"b\tc\"\td\n")
+    );
+
+    assert!(error(parse("a", false, ";;")).contains("Expected a single character, found `;;`"));
+}
+
+#[test]
+fn maps_rows_to_the_header() {
+    assert_eq!(
+        parse("name,age\nAnn,31\nBob,42\n", true, ",").unwrap(),
+        Variable::Array(vec![
+            map(&[("name", "Ann"), ("age", "31")]),
+            map(&[("name", "Bob"), ("age", "42")]),
+        ])
+    );
+
+    // Without a header rows can be any length, but with one they must match.
+    assert_eq!(
+        parse("a,b\nc\n", false, ",").unwrap(),
+        Variable::Array(vec![strs(&["a", "b"]), strs(&["c"])])
+    );
+    assert!(error(parse("a,b\nc\n", true, ",")).contains("Invalid CSV"));
+
+    let err = error(parse("name,age,name\nAnn,31,Bob\n", true, ","));
+    assert!(
+        err.contains("`name` is in the header more than once"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn writes_every_key_of_every_map() {
+    assert_eq!(
+        stringify(
+            vec![map(&[("b", "1")]), map(&[("a", "2"), ("c", "3")])],
+            ","
+        )
+        .unwrap(),
+        str("a,b,c\n,1,\n2,,3\n")
+    );
+
+    let err = error(stringify(vec![map(&[("a", "1")]), strs(&["2"])], ","));
+    assert!(err.contains("Expected a map, found an array"), "{}", err);
+}
+
+#[test]
+fn streams_rows_one_at_a_time() {
+    let path = std::env::temp_dir().join(format!("shrimp-csv-{}.csv", std::process::id()));
+    // The last row is broken, but the ones before it are read first.
+    fs::write(&path, "name,age\nAnn,31\nBob,42\nbroken\n").unwrap();
+
+    let out = SharedBuffer::new();
+    let mut shrimp = shrimp();
+    shrimp.set_stdout(out.clone());
+
+    let err = shrimp
+        .call("names", vec![str(&path.display().to_string())])
+        .unwrap_err();
+
+    assert_eq!(out.contents(), "Ann\nBob\n");
+    assert!(format!("{:#}", err).contains("Invalid CSV"));
+}
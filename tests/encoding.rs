@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use encoding
+@base64(data url_safe) { encoding.base64_encode(data url_safe) -> out return out }
+@unbase64(text url_safe) { encoding.base64_decode(text url_safe) -> out return out }
+@hex(data) { encoding.hex_encode(data) -> out return out }
+@unhex(text) { encoding.hex_decode(text) -> out return out }
+@url(text) { encoding.url_encode(text) -> out return out }
+@unurl(text) { encoding.url_decode(text) -> out return out }
+@query(text) { encoding.query_decode(text) -> out return out }
+@md5(data) { encoding.md5(data) -> out return out }
+@sha1(data) { encoding.sha1(data) -> out return out }
+@sha256(data) { encoding.sha256(data) -> out return out }
+@crc32(data) { encoding.crc32(data) -> out return out }
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn bytes(bytes: &[u8]) -> Variable {
+    Variable::Array(bytes.iter().map(|&x| Variable::Num(x as f64)).collect())
+}
+
+fn error(result: anyhow::Result<Variable>) -> String {
+    format!("{:#}", result.unwrap_err())
+}
+
+#[test]
+fn digests_match_known_vectors() {
+    let mut shrimp = shrimp();
+
+    for (function, data, digest) in [
+        ("md5", "", "d41d8cd98f00b204e9800998ecf8427e"),
+        ("md5", "abc", "900150983cd24fb0d6963f7d28e17f72"),
+        ("sha1", "abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+        (
+            "sha256",
+            "abc",
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+    ] {
+        assert_eq!(
+            shrimp.call(function, vec![str(data)]).unwrap(),
+            str(digest),
+            "{}({:?})",
+            function,
+            data
+        );
+        // Bytes hash the same as the string they spell.
+        assert_eq!(
+            shrimp.call(function, vec![bytes(data.as_bytes())]).unwrap(),
+            str(digest)
+        );
+    }
+
+    // The standard check value for CRC-32.
+    assert_eq!(
+        shrimp.call("crc32", vec![str("123456789")]).unwrap(),
+        Variable::Num(0xCBF43926u32 as f64)
+    );
+}
+
+#[test]
+fn base64_in_both_alphabets() {
+    let mut shrimp = shrimp();
+    let standard = Variable::Bool(false);
+    let url_safe = Variable::Bool(true);
+
+    assert_eq!(
+        shrimp
+            .call("base64", vec![str("foob"), standard.clone()])
+            .unwrap(),
+        str("Zm9vYg==")
+    );
+    // The padding is optional when decoding.
+    for text in ["Zm9vYg==", "Zm9vYg"] {
+        assert_eq!(
+            shrimp
+                .call("unbase64", vec![str(text), standard.clone()])
+                .unwrap(),
+            bytes(b"foob")
+        );
+    }
+
+    // These bytes are where the two alphabets differ.
+    let data = bytes(&[251, 255]);
+    assert_eq!(
+        shrimp
+            .call("base64", vec![data.clone(), standard.clone()])
+            .unwrap(),
+        str("+/8=")
+    );
+    assert_eq!(
+        shrimp
+            .call("base64", vec![data.clone(), url_safe.clone()])
+            .unwrap(),
+        str("-_8")
+    );
+    for text in ["-_8", "-_8="] {
+        assert_eq!(
+            shrimp
+                .call("unbase64", vec![str(text), url_safe.clone()])
+                .unwrap(),
+            data
+        );
+    }
+
+    let err = error(shrimp.call("unbase64", vec![str("-_8"), standard]));
+    assert!(err.contains("base64"), "{}", err);
+}
+
+#[test]
+fn hex_round_trips() {
+    let mut shrimp = shrimp();
+
+    let data = bytes(&[0, 15, 16, 171, 255]);
+    assert_eq!(
+        shrimp.call("hex", vec![data.clone()]).unwrap(),
+        str("000f10abff")
+    );
+    assert_eq!(shrimp.call("unhex", vec![str("000F10abFF")]).unwrap(), data);
+}
+
+#[test]
+fn url_and_query_decoding_want_utf8() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        shrimp.call("url", vec![str("a b/é")]).unwrap(),
+        str("a%20b%2F%C3%A9")
+    );
+    assert_eq!(
+        shrimp.call("unurl", vec![str("a%20b%2F%C3%A9")]).unwrap(),
+        str("a b/é")
+    );
+
+    let mut query = BTreeMap::new();
+    query.insert("a".to_string(), str("1"));
+    query.insert("b".to_string(), str("two words"));
+    query.insert("c".to_string(), str(""));
+    query.insert("é".to_string(), str("&"));
+    assert_eq!(
+        shrimp
+            .call("query", vec![str("?a=1&b=two+words&c&%C3%A9=%26")])
+            .unwrap(),
+        Variable::Map(query)
+    );
+
+    for (function, text) in [("unurl", "%FF"), ("query", "a=%FF"), ("query", "%FF=a")] {
+        let err = error(shrimp.call(function, vec![str(text)]));
+        assert!(
+            err.contains("Invalid UTF-8"),
+            "{} {}: {}",
+            function,
+            text,
+            err
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+@format0(template) { format(template) -> out return out }
+@format2(template a b) { format(template a b) -> out return out }
+@named(template) {
+    decl name \"Shrimp\"
+    decl items [1 2 3]
+    use map
+    map.new(\"name\" \"Prawn\") -> user
+    format(template) -> out
+    return out
+}
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn num(n: f64) -> Variable {
+    Variable::Num(n)
+}
+
+fn format2(shrimp: &mut Interpreter, template: &str, a: Variable, b: Variable) -> String {
+    match shrimp.call("format2", vec![str(template), a, b]).unwrap() {
+        Variable::Str(out) => out,
+        any => panic!("expected a string, found {:?}", any),
+    }
+}
+
+fn error(shrimp: &mut Interpreter, template: &str) -> String {
+    let err = shrimp
+        .call("format2", vec![str(template), num(1.0), str("a")])
+        .unwrap_err();
+    format!("{:#}", err)
+}
+
+#[test]
+fn fills_positional_placeholders() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        format2(&mut shrimp, "{} of {}", num(3.0), num(10.0)),
+        "3 of 10"
+    );
+    assert_eq!(
+        format2(&mut shrimp, "{1}, {0}, {1}", str("a"), str("b")),
+        "b, a, b"
+    );
+    assert_eq!(
+        format2(&mut shrimp, "{{{}}} }}", str("x"), Variable::Void),
+        "{x} }"
+    );
+}
+
+#[test]
+fn evaluates_named_and_expression_placeholders() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        shrimp
+            .call(
+                "named",
+                vec![str(
+                    "{name} has {len(items)} items, and {user.name} has {add(len(items) 1)}"
+                )]
+            )
+            .unwrap(),
+        str("Shrimp has 3 items, and Prawn has 4")
+    );
+    // A `:` inside a string isn't the start of a spec.
+    assert_eq!(
+        shrimp
+            .call("named", vec![str("{con(name \":\"):>8}")])
+            .unwrap(),
+        str(" Shrimp:")
+    );
+
+    let err = shrimp.call("named", vec![str("{nowhere}")]).unwrap_err();
+    assert!(format!("{:#}", err).contains("placeholder `{nowhere}`"));
+}
+
+#[test]
+fn lays_out_values() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        format2(&mut shrimp, "{:<6}|{:>8.2}", str("apple"), num(1.5)),
+        "apple |    1.50"
+    );
+    assert_eq!(
+        format2(&mut shrimp, "{:*^7}{:05}", str("mid"), num(-42.0)),
+        "**mid**-0042"
+    );
+    // Numbers go right by default, and strings left.
+    assert_eq!(
+        format2(&mut shrimp, "[{:4}][{:4}]", num(7.0), str("ab")),
+        "[   7][ab  ]"
+    );
+    // Precision cuts strings short, by characters.
+    assert_eq!(
+        format2(&mut shrimp, "{:.3}{:.0}", str("🦐🦐🦐🦐"), num(2.5)),
+        "🦐🦐🦐2"
+    );
+    assert_eq!(
+        format2(&mut shrimp, "{:65535}", str(""), Variable::Void).len(),
+        65535
+    );
+}
+
+#[test]
+fn bad_templates_are_errors() {
+    let mut shrimp = shrimp();
+
+    for (template, message) in [
+        ("{:x}", "unknown format spec `x`"),
+        ("{:5.}", "missing precision in `5.`"),
+        (
+            "{:65536}",
+            "`65536` is too large in `65536` (the most is 65535)",
+        ),
+        ("{:.70000}", "`70000` is too large"),
+        ("{:99999999999999999999999}", "is too large"),
+        ("{", "unclosed `{`"),
+        ("}", "unmatched `}`"),
+        ("{} {} {}", "Missing an argument"),
+        ("{5}", "len is 2 but index is 5"),
+    ] {
+        let err = error(&mut shrimp, template);
+        assert!(err.contains(message), "{}: {}", template, err);
+    }
+}
@@ -0,0 +1,185 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+@fetch(url) {
+    use internet
+    internet.get(url) -> out
+    return out
+}
+@send(url) {
+    use internet
+    internet.request(\"POST\" url) -> req
+    internet.header(req \"X-Shrimp\" \"yes\") -> req
+    internet.body(req \"payload\") -> req
+    internet.send(req) -> res
+    return res
+}
+@download(url) {
+    use internet
+    internet.request(\"GET\" url) -> req
+    internet.bytes(req true) -> req
+    internet.send(req) -> res
+    return res
+}";
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+/// Answers `count` requests on a local port, echoing back the method,
+/// path and body. Hands back the address, and the raw requests once
+/// they have all been answered.
+fn serve(count: usize) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let mut requests = vec![];
+
+        for _ in 0..count {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut head = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                head.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let body = String::from_utf8(body).unwrap();
+
+            let mut words = head.split(' ');
+            let echo = format!(
+                "{} {} {}",
+                words.next().unwrap(),
+                words.next().unwrap(),
+                body
+            );
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 201 Created\r\nX-Test: yes\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                echo.len(),
+                echo
+            )
+            .unwrap();
+
+            requests.push(head);
+        }
+
+        requests
+    });
+
+    (addr, handle)
+}
+
+#[test]
+fn gets_the_body() {
+    let (addr, server) = serve(1);
+
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+
+    let url = format!("{}/hello?x=1", addr);
+    assert_eq!(
+        shrimp.call("fetch", vec![str(&url)]).unwrap(),
+        str("GET /hello?x=1 ")
+    );
+    server.join().unwrap();
+}
+
+#[test]
+fn sends_requests_and_reads_responses() {
+    let (addr, server) = serve(1);
+
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+
+    let url = format!("{}/submit", addr);
+    let res = match shrimp.call("send", vec![str(&url)]).unwrap() {
+        Variable::Map(res) => res,
+        any => panic!("expected a response, found {:?}", any),
+    };
+
+    assert_eq!(res["status"], Variable::Num(201.0));
+    assert_eq!(res["body"], str("POST /submit payload"));
+    // Only there when asked for.
+    assert!(!res.contains_key("bytes"));
+    match &res["headers"] {
+        Variable::Map(headers) => assert_eq!(headers["x-test"], str("yes")),
+        any => panic!("expected headers, found {:?}", any),
+    }
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].to_ascii_lowercase().contains("x-shrimp: yes"));
+}
+
+#[test]
+fn reads_bytes_when_asked_to() {
+    let (addr, server) = serve(1);
+
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+
+    let res = match shrimp.call("download", vec![str(&addr)]).unwrap() {
+        Variable::Map(res) => res,
+        any => panic!("expected a response, found {:?}", any),
+    };
+
+    assert_eq!(
+        res["bytes"],
+        Variable::Array(
+            b"GET / "
+                .iter()
+                .map(|byte| Variable::Num(*byte as f64))
+                .collect()
+        )
+    );
+    server.join().unwrap();
+}
+
+#[test]
+fn failing_to_connect_is_an_error() {
+    // Bound and dropped straight away, so nothing is listening there.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    drop(listener);
+
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+
+    let err = shrimp.call("fetch", vec![str(&url)]).unwrap_err();
+    assert!(format!("{:#}", err).contains("Request to"));
+}
+
+#[test]
+fn works_from_inside_an_async_runtime() {
+    let (addr, server) = serve(1);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let out = runtime.block_on(async {
+        let mut shrimp = Interpreter::new();
+        shrimp.load(SCRIPT).unwrap();
+        shrimp.call("fetch", vec![str(&format!("{}/async", addr))])
+    });
+
+    assert_eq!(out.unwrap(), str("GET /async "));
+    server.join().unwrap();
+}
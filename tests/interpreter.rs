@@ -0,0 +1,117 @@
+use std::io::Cursor;
+
+use shrimp::{Err, Interpreter, SharedBuffer, Variable};
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+#[test]
+fn calls_shrimp_functions() {
+    let mut shrimp = Interpreter::new();
+    shrimp
+        .load("@greet(name) { con(\"Hello \" name) -> out return out }")
+        .unwrap();
+
+    assert_eq!(
+        shrimp.call("greet", vec![str("Shrimp")]).unwrap(),
+        str("Hello Shrimp")
+    );
+    assert_eq!(shrimp.functions(), vec![("greet".to_string(), 1)]);
+}
+
+#[test]
+fn calling_a_missing_function_fails() {
+    let mut shrimp = Interpreter::new();
+
+    assert!(shrimp.call("nope", vec![]).is_err());
+}
+
+#[test]
+fn shrimp_can_call_registered_natives() {
+    let mut shrimp = Interpreter::new();
+    shrimp.register_fn("double", |args, _| match &args[..] {
+        [Variable::Num(num)] => Ok(Variable::Num(num * 2.0)),
+        _ => Ok(Variable::Void),
+    });
+    shrimp
+        .load("@main() { double(21) -> out return out }")
+        .unwrap();
+
+    assert_eq!(shrimp.call("main", vec![]).unwrap(), Variable::Num(42.0));
+}
+
+#[test]
+fn shrimp_can_read_host_values() {
+    let mut shrimp = Interpreter::new();
+    shrimp.set("name", str("host"));
+    shrimp.load("@main() { return name }").unwrap();
+
+    assert_eq!(shrimp.call("main", vec![]).unwrap(), str("host"));
+    assert_eq!(shrimp.get("name"), Some(&str("host")));
+}
+
+#[test]
+fn output_can_be_captured() {
+    let out = SharedBuffer::new();
+
+    let mut shrimp = Interpreter::new();
+    shrimp.set_stdout(out.clone());
+    shrimp
+        .load("@main() { println(\"Hello\") print(1 2) }")
+        .unwrap();
+    shrimp.call("main", vec![]).unwrap();
+
+    assert_eq!(out.take(), "Hello\n12");
+    assert_eq!(out.contents(), "");
+}
+
+#[test]
+fn output_is_discarded_by_default() {
+    let mut shrimp = Interpreter::new();
+    shrimp
+        .load("@main() { println(\"Nobody sees this\") }")
+        .unwrap();
+
+    assert!(shrimp.call("main", vec![]).is_ok());
+}
+
+#[test]
+fn input_can_be_supplied() {
+    let mut shrimp = Interpreter::new();
+    shrimp.set_stdin(Cursor::new("Bob\n"));
+    shrimp
+        .load("@main() { use io io.input() -> name return name }")
+        .unwrap();
+
+    assert_eq!(shrimp.call("main", vec![]).unwrap(), str("Bob"));
+}
+
+#[test]
+fn exit_is_handed_back() {
+    let mut shrimp = Interpreter::new();
+    shrimp
+        .load("@main() { try { exit(3) } catch err { println(err) } }")
+        .unwrap();
+
+    let err = shrimp.call("main", vec![]).unwrap_err();
+    assert_eq!(Err::exit_code(&err), Some(3));
+}
+
+#[test]
+fn each_interpreter_has_its_own_random_generator() {
+    let script = "@seed() { use random random.seed(42) }
+        @draw() { use random random.int(1 1000000) -> out return out }";
+
+    let mut first = Interpreter::new();
+    let mut second = Interpreter::new();
+    first.load(script).unwrap();
+    second.load(script).unwrap();
+
+    first.call("seed", vec![]).unwrap();
+    second.call("seed", vec![]).unwrap();
+    let a = first.call("draw", vec![]).unwrap();
+    let b = second.call("draw", vec![]).unwrap();
+
+    assert_eq!(a, b);
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use json
+@parse(text) { json.parse(text) -> out return out }
+@stringify(value pretty) { json.stringify(value pretty) -> out return out }
+@round_trip(text) {
+    json.parse(text) -> value
+    json.stringify(value) -> out
+    return out
+}
+@helper() { }
+@stringify_function() { json.stringify(helper) }
+@stringify_native() { json.stringify(json.parse) }
+@stringify_handle() {
+    use server
+    server.new() -> app
+    json.stringify(app)
+}
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn num(n: f64) -> Variable {
+    Variable::Num(n)
+}
+
+fn stringify(shrimp: &mut Interpreter, value: Variable) -> anyhow::Result<Variable> {
+    shrimp.call("stringify", vec![value, Variable::Bool(false)])
+}
+
+fn error(result: anyhow::Result<Variable>) -> String {
+    format!("{:#}", result.unwrap_err())
+}
+
+#[test]
+fn parses_into_shrimp_values() {
+    let mut shrimp = shrimp();
+
+    let mut inner = BTreeMap::new();
+    inner.insert("c".to_string(), num(-3.0));
+    let mut outer = BTreeMap::new();
+    outer.insert(
+        "a".to_string(),
+        Variable::Array(vec![
+            num(1.0),
+            num(2.5),
+            str("x\n\u{1F990}"),
+            Variable::Bool(true),
+            Variable::Void,
+        ]),
+    );
+    outer.insert("b".to_string(), Variable::Map(inner));
+
+    assert_eq!(
+        shrimp
+            .call(
+                "parse",
+                vec![str(
+                    r#" {"b": {"c": -3e0}, "a": [1, 2.5, "x\n🦐", true, null]} "#
+                )]
+            )
+            .unwrap(),
+        Variable::Map(outer)
+    );
+}
+
+#[test]
+fn round_trips() {
+    let mut shrimp = shrimp();
+
+    for text in [
+        r#"{"a":[1,2.5,"x\"y",true,null],"b":{"c":-3,"d":{}},"e":[]}"#,
+        r#"[1e100,-0.001,9007199254740992]"#,
+        r#""just a string""#,
+        "null",
+    ] {
+        assert_eq!(
+            shrimp.call("round_trip", vec![str(text)]).unwrap(),
+            str(text)
+        );
+    }
+
+    assert_eq!(
+        shrimp
+            .call("stringify", vec![str("x"), Variable::Bool(true)])
+            .unwrap(),
+        str("\"x\"")
+    );
+    let value = shrimp.call("parse", vec![str(r#"{"a":[1]}"#)]).unwrap();
+    assert_eq!(
+        shrimp
+            .call("stringify", vec![value, Variable::Bool(true)])
+            .unwrap(),
+        str("{\n  \"a\": [\n    1\n  ]\n}")
+    );
+}
+
+#[test]
+fn bad_json_says_where() {
+    let mut shrimp = shrimp();
+
+    let err = error(shrimp.call("parse", vec![str("{\n  \"a\": 1,\n  \"b\": ]\n}")]));
+    assert!(
+        err.contains("Invalid JSON at line 3, column 8: expected value"),
+        "{}",
+        err
+    );
+
+    let err = error(shrimp.call("parse", vec![str("[1, 2")]));
+    assert!(
+        err.contains("Invalid JSON at line 1, column 5: EOF while parsing a list"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn only_json_values_can_be_written() {
+    let mut shrimp = shrimp();
+
+    for (value, words) in [
+        (f64::NAN, "NaN"),
+        (f64::INFINITY, "inf"),
+        (f64::NEG_INFINITY, "-inf"),
+    ] {
+        let err = error(stringify(&mut shrimp, num(value)));
+        assert!(
+            err.contains(&format!("{} can't be written as JSON", words)),
+            "{}",
+            err
+        );
+    }
+
+    // Nested ones too.
+    let err = error(stringify(&mut shrimp, Variable::Array(vec![num(f64::NAN)])));
+    assert!(err.contains("NaN can't be written as JSON"), "{}", err);
+
+    for function in ["stringify_function", "stringify_native"] {
+        let err = error(shrimp.call(function, vec![]));
+        assert!(
+            err.contains("a function can't be written as JSON"),
+            "{}",
+            err
+        );
+    }
+    let err = error(shrimp.call("stringify_handle", vec![]));
+    assert!(err.contains("a handle can't be written as JSON"), "{}", err);
+}
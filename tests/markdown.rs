@@ -0,0 +1,77 @@
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use html
+@convert(markdown) { html.markdown_to_html(markdown) -> out return out }
+@trusted(markdown) { html.markdown_to_html(markdown true) -> out return out }
+";
+
+fn convert(function: &str, markdown: &str) -> String {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+
+    match shrimp
+        .call(function, vec![Variable::Str(markdown.to_string())])
+        .unwrap()
+    {
+        Variable::Html(html) => html,
+        any => panic!("expected HTML, found {:?}", any),
+    }
+}
+
+#[test]
+fn converts_commonmark_tables_and_strikethrough() {
+    assert_eq!(
+        convert("convert", "# Title\n\n*Hi* ~~there~~, [home](/index.html)"),
+        "<h1>Title</h1>\n<p><em>Hi</em> <del>there</del>, <a href=\"/index.html\">home</a></p>\n"
+    );
+    assert!(convert("convert", "| a | b |\n|---|---|\n| 1 | 2 |").contains("<td>1</td>"));
+}
+
+#[test]
+fn escapes_html_unless_trusted() {
+    let markdown = "Hello <b onclick=\"steal()\">there</b>\n\n<script>alert(1)</script>";
+
+    let out = convert("convert", markdown);
+    assert!(!out.contains("<b"), "{}", out);
+    assert!(!out.contains("<script>"), "{}", out);
+    assert!(
+        out.contains("&lt;script&gt;alert(1)&lt;/script&gt;"),
+        "{}",
+        out
+    );
+
+    let out = convert("trusted", markdown);
+    assert!(out.contains("<b onclick=\"steal()\">there</b>"), "{}", out);
+    assert!(out.contains("<script>alert(1)</script>"), "{}", out);
+}
+
+#[test]
+fn drops_urls_that_run_code() {
+    for url in [
+        "javascript:alert(1)",
+        "JavaScript:alert(1)",
+        // Entities are decoded before the URL is checked.
+        "&#106;avascript:alert(1)",
+        "vbscript:msgbox(1)",
+        "data:text/html;base64,PHNjcmlwdD4=",
+    ] {
+        // Even trusted Markdown doesn't get these, from links and images alike.
+        for function in ["convert", "trusted"] {
+            let out = convert(function, &format!("[click]({}) ![pic]({})", url, url));
+            assert!(
+                out.contains("<a href=\"\">click</a>"),
+                "{} {}: {}",
+                function,
+                url,
+                out
+            );
+            assert!(out.contains("<img src=\"\" alt=\"pic\" />"), "{}", out);
+        }
+    }
+
+    assert!(
+        convert("convert", "[ok](https://example.com/javascript:)")
+            .contains("href=\"https://example.com/javascript:\"")
+    );
+}
@@ -0,0 +1,102 @@
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use math
+@fixed(num digits) { math.fixed(num digits) -> out return out }
+@gcd(a b) { math.gcd(a b) -> out return out }
+@lcm(a b) { math.lcm(a b) -> out return out }
+@bits(a b) {
+    decl out []
+    math.bit_and(a b) -> and
+    math.bit_or(a b) -> or
+    math.bit_xor(a b) -> xor
+    math.bit_not(a) -> not
+    push(out and or xor not) -> out
+    return out
+}
+@shl(a b) { math.shl(a b) -> out return out }
+@shr(a b) { math.shr(a b) -> out return out }
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn num(n: f64) -> Variable {
+    Variable::Num(n)
+}
+
+fn call(shrimp: &mut Interpreter, name: &str, args: &[f64]) -> anyhow::Result<Variable> {
+    shrimp.call(name, args.iter().copied().map(num).collect())
+}
+
+fn err(shrimp: &mut Interpreter, name: &str, args: &[f64]) -> String {
+    format!("{:#}", call(shrimp, name, args).unwrap_err())
+}
+
+#[test]
+fn fixed_pads_and_rounds() {
+    let mut shrimp = shrimp();
+
+    for (n, digits, out) in [
+        (0.1 + 0.2, 2.0, "0.30"),
+        (1.5, 0.0, "2"),
+        (-2.0, 3.0, "-2.000"),
+        (1.0 / 3.0, 5.0, "0.33333"),
+    ] {
+        assert_eq!(
+            call(&mut shrimp, "fixed", &[n, digits]).unwrap(),
+            Variable::Str(out.to_string())
+        );
+    }
+
+    match call(&mut shrimp, "fixed", &[1.5, 100.0]).unwrap() {
+        Variable::Str(out) => assert_eq!(out.len(), "1.".len() + 100),
+        any => panic!("expected a string, found {:?}", any),
+    }
+}
+
+#[test]
+fn fixed_rejects_bad_digits() {
+    let mut shrimp = shrimp();
+
+    // Huge precisions used to abort the whole process.
+    assert!(err(&mut shrimp, "fixed", &[1.5, 70000.0]).contains("Can't show 70000 decimal places"));
+    assert!(err(&mut shrimp, "fixed", &[1.5, 101.0]).contains("Can't show 101 decimal places"));
+    assert!(err(&mut shrimp, "fixed", &[1.5, -1.0]).contains("Can't show -1 decimal places"));
+    assert!(err(&mut shrimp, "fixed", &[1.5, 0.5]).contains("Expected a whole number"));
+}
+
+#[test]
+fn integer_helpers() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(call(&mut shrimp, "gcd", &[12.0, -18.0]).unwrap(), num(6.0));
+    assert_eq!(call(&mut shrimp, "gcd", &[0.0, 0.0]).unwrap(), num(0.0));
+    assert_eq!(call(&mut shrimp, "lcm", &[4.0, -6.0]).unwrap(), num(12.0));
+    assert_eq!(call(&mut shrimp, "lcm", &[0.0, 5.0]).unwrap(), num(0.0));
+
+    assert_eq!(
+        call(&mut shrimp, "bits", &[12.0, 10.0]).unwrap(),
+        Variable::Array(vec![num(8.0), num(14.0), num(6.0), num(-13.0)])
+    );
+    assert_eq!(call(&mut shrimp, "shl", &[3.0, 4.0]).unwrap(), num(48.0));
+    assert_eq!(call(&mut shrimp, "shr", &[-16.0, 2.0]).unwrap(), num(-4.0));
+}
+
+#[test]
+fn integer_helpers_reject_out_of_range_input() {
+    let mut shrimp = shrimp();
+
+    assert!(err(&mut shrimp, "gcd", &[1.5, 2.0]).contains("Expected a whole number, found 1.5"));
+    assert!(err(&mut shrimp, "bits", &[f64::NAN, 1.0]).contains("Expected a whole number"));
+    assert!(err(&mut shrimp, "gcd", &[1e300, 2.0]).contains("Expected a whole number"));
+    assert!(err(&mut shrimp, "shl", &[1.0, 64.0]).contains("Can't shift by 64 bits"));
+    assert!(err(&mut shrimp, "shr", &[1.0, -1.0]).contains("Can't shift by -1 bits"));
+
+    let big = 2f64.powi(62);
+    assert!(err(&mut shrimp, "lcm", &[big, 3.0]).contains("least common multiple"));
+    assert!(err(&mut shrimp, "gcd", &[i64::MIN as f64, 0.0]).contains("greatest common divisor"));
+}
@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use shrimp::{Interpreter, Variable};
+
+/// A fresh directory for one test's Shrimp files.
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("shrimp-modules-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(dir: &Path, name: &str, source: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, source).unwrap();
+    path
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+#[test]
+fn imports_by_name_and_by_path() {
+    let dir = dir("paths");
+    write(
+        &dir,
+        "utils.imp",
+        "@shout(x) { con(x \"!\") -> out return out }",
+    );
+    write(
+        &dir,
+        "lib/strings.imp",
+        "@twice(x) { con(x x) -> out return out }",
+    );
+    let main = write(
+        &dir,
+        "main.imp",
+        "use utils\nuse \"lib/strings.imp\"\n@main() { twice(\"hi\") -> x shout(x) -> out return out }",
+    );
+
+    let mut shrimp = Interpreter::new();
+    shrimp.load_file(&main).unwrap();
+
+    assert_eq!(shrimp.call("main", vec![]).unwrap(), str("hihi!"));
+}
+
+#[test]
+fn imports_inside_functions() {
+    let dir = dir("inside");
+    write(
+        &dir,
+        "utils.imp",
+        "@shout(x) { con(x \"!\") -> out return out }",
+    );
+    let main = write(
+        &dir,
+        "main.imp",
+        "@main() { use utils shout(\"hey\") -> out return out }",
+    );
+
+    let mut shrimp = Interpreter::new();
+    shrimp.load_file(&main).unwrap();
+
+    assert_eq!(shrimp.call("main", vec![]).unwrap(), str("hey!"));
+}
+
+#[test]
+fn shared_imports_are_not_cycles() {
+    // main uses left and right, which both use base.
+    let dir = dir("diamond");
+    write(&dir, "base.imp", "@base() { return 1 }");
+    write(&dir, "left.imp", "use base\n@left() { return 2 }");
+    write(&dir, "right.imp", "use base\n@right() { return 3 }");
+    let main = write(
+        &dir,
+        "main.imp",
+        "use left\nuse right\n@main() { base() -> out return out }",
+    );
+
+    let mut shrimp = Interpreter::new();
+    shrimp.load_file(&main).unwrap();
+
+    assert_eq!(shrimp.call("main", vec![]).unwrap(), Variable::Num(1.0));
+}
+
+#[test]
+fn import_cycles_are_errors() {
+    let dir = dir("cycle");
+    write(&dir, "a.imp", "use b\n@a() { }");
+    write(&dir, "b.imp", "use a\n@b() { }");
+
+    let mut shrimp = Interpreter::new();
+    let err = format!("{:#}", shrimp.load_file(dir.join("a.imp")).unwrap_err());
+
+    assert!(err.contains("Import cycle detected"), "{}", err);
+    assert!(err.contains("a.imp -> "), "{}", err);
+    assert!(err.contains("b.imp -> "), "{}", err);
+}
+
+#[test]
+fn missing_modules_are_errors() {
+    let dir = dir("missing");
+    let main = write(&dir, "main.imp", "use nowhere\n@main() { }");
+
+    let mut shrimp = Interpreter::new();
+    let err = format!("{:#}", shrimp.load_file(&main).unwrap_err());
+
+    assert!(
+        err.contains("Could not find a module at `nowhere.imp`"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn legacy_prefixes_only_cover_the_old_names() {
+    let mut shrimp = Interpreter::new();
+    shrimp
+        .load("use io\nuse html\nuse json\nuse internet\n@main() { }")
+        .unwrap();
+
+    for name in ["io_read", "el_p", "json_parse", "internet_get"] {
+        assert!(shrimp.get(name).is_some(), "{} is missing", name);
+    }
+    for name in ["io_open", "el_article", "internet_request"] {
+        assert!(shrimp.get(name).is_none(), "{} is defined", name);
+    }
+    assert!(shrimp.get("io").is_some());
+}
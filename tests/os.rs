@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use os
+@sh(script input) {
+    decl args [\"-c\"]
+    push(args script) -> args
+    os.run(\"sh\" args input) -> res
+    return res
+}
+@lines(script) {
+    decl args [\"-c\"]
+    push(args script) -> args
+    decl out []
+    for os.stream(\"sh\" args) => line {
+        push(out line) -> out
+    }
+    return out
+}
+@env(name) { os.env(name) -> out return out }
+@env_vars() { os.env_vars() -> out return out }
+@set_env(name value) { os.set_env(name value) }
+@remove_env(name) { os.remove_env(name) }
+@missing() { os.run(\"shrimp-no-such-program\") }
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn map(var: Variable) -> BTreeMap<String, Variable> {
+    match var {
+        Variable::Map(map) => map,
+        any => panic!("expected a map, found {:?}", any),
+    }
+}
+
+#[test]
+fn runs_programs() {
+    let mut shrimp = shrimp();
+
+    let res = map(shrimp
+        .call("sh", vec![str("cat; echo oops >&2; exit 3"), str("hello")])
+        .unwrap());
+
+    assert_eq!(res["code"], Variable::Num(3.0));
+    assert_eq!(res["stdout"], str("hello"));
+    assert_eq!(res["stderr"], str("oops\n"));
+
+    let err = shrimp.call("missing", vec![]).unwrap_err();
+    assert!(format!("{:#}", err).contains("Failed to run `shrimp-no-such-program`"));
+}
+
+#[test]
+fn streams_lines() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        shrimp
+            .call("lines", vec![str("echo one; echo two")])
+            .unwrap(),
+        Variable::Array(vec![str("one"), str("two")])
+    );
+
+    // Failing is an error once every line has been read, with stderr.
+    let err = shrimp
+        .call("lines", vec![str("echo one; echo broken >&2; exit 1")])
+        .unwrap_err();
+    let err = format!("{:#}", err);
+    assert!(err.contains("`sh` failed"), "{}", err);
+    assert!(err.contains("broken"), "{}", err);
+}
+
+#[test]
+fn environment_changes_stay_in_the_interpreter() {
+    let mut shrimp = shrimp();
+    let name = format!("SHRIMP_OS_TEST_{}", std::process::id());
+
+    shrimp
+        .call("set_env", vec![str(&name), str("changed")])
+        .unwrap();
+
+    assert_eq!(
+        shrimp.call("env", vec![str(&name)]).unwrap(),
+        str("changed")
+    );
+    assert_eq!(
+        map(shrimp.call("env_vars", vec![]).unwrap())[&name],
+        str("changed")
+    );
+    // Programs that are run see the change...
+    let script = format!("printf %s \"${}\"", name);
+    let res = map(shrimp.call("sh", vec![str(&script), str("")]).unwrap());
+    assert_eq!(res["stdout"], str("changed"));
+    // ...but the process and other interpreters don't.
+    assert!(std::env::var(&name).is_err());
+    assert_eq!(
+        self::shrimp().call("env", vec![str(&name)]).unwrap(),
+        Variable::Void
+    );
+
+    // PATH is set for the whole process, so removing it can be checked.
+    shrimp.call("remove_env", vec![str("PATH")]).unwrap();
+    assert_eq!(
+        shrimp.call("env", vec![str("PATH")]).unwrap(),
+        Variable::Void
+    );
+    assert!(!map(shrimp.call("env_vars", vec![]).unwrap()).contains_key("PATH"));
+    assert!(std::env::var("PATH").is_ok());
+}
@@ -0,0 +1,123 @@
+use std::fs;
+
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use path
+@join(a b c) { path.join(a b c) -> out return out }
+@parts(file) {
+    decl out []
+    path.parent(file) -> parent
+    path.file_name(file) -> name
+    path.stem(file) -> stem
+    path.extension(file) -> extension
+    push(out parent name stem extension) -> out
+    return out
+}
+@with_extension(file extension) { path.with_extension(file extension) -> out return out }
+@normalize(file) { path.normalize(file) -> out return out }
+@glob(pattern) { path.glob(pattern) -> out return out }
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn call(shrimp: &mut Interpreter, name: &str, args: &[&str]) -> Variable {
+    shrimp
+        .call(name, args.iter().map(|arg| str(arg)).collect())
+        .unwrap()
+}
+
+#[test]
+fn joins_and_takes_apart_paths() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        call(&mut shrimp, "join", &["reports", "2021", "summary.tar.gz"]),
+        str("reports/2021/summary.tar.gz")
+    );
+    // An absolute part replaces everything before it.
+    assert_eq!(
+        call(&mut shrimp, "join", &["reports", "/tmp", "x"]),
+        str("/tmp/x")
+    );
+
+    assert_eq!(
+        call(&mut shrimp, "parts", &["reports/summary.tar.gz"]),
+        Variable::Array(vec![
+            str("reports"),
+            str("summary.tar.gz"),
+            str("summary.tar"),
+            str("gz"),
+        ])
+    );
+    assert_eq!(
+        call(&mut shrimp, "parts", &["/"]),
+        Variable::Array(vec![Variable::Void; 4])
+    );
+
+    assert_eq!(
+        call(&mut shrimp, "with_extension", &["notes.txt", "md"]),
+        str("notes.md")
+    );
+}
+
+#[test]
+fn normalizes_without_the_file_system() {
+    let mut shrimp = shrimp();
+
+    for (path, normal) in [
+        ("docs/./drafts/../final.md", "docs/final.md"),
+        ("../up/../../more", "../../more"),
+        ("/../etc", "/etc"),
+        ("a/..", "."),
+    ] {
+        assert_eq!(call(&mut shrimp, "normalize", &[path]), str(normal));
+    }
+}
+
+#[test]
+fn globs_sorted_paths() {
+    let dir = std::env::temp_dir().join(format!("shrimp-path-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("sub/deeper")).unwrap();
+    for file in [
+        "b.imp",
+        "a.imp",
+        "notes.txt",
+        "sub/c.imp",
+        "sub/deeper/d.imp",
+    ] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+
+    let mut shrimp = shrimp();
+    let dir = dir.display().to_string();
+
+    let pattern = format!("{}/**/*.imp", dir);
+    assert_eq!(
+        call(&mut shrimp, "glob", &[&pattern]),
+        Variable::Array(
+            ["a.imp", "b.imp", "sub/c.imp", "sub/deeper/d.imp"]
+                .iter()
+                .map(|file| str(&format!("{}/{}", dir, file)))
+                .collect()
+        )
+    );
+
+    let pattern = format!("{}/*.txt", dir);
+    assert_eq!(
+        call(&mut shrimp, "glob", &[&pattern]),
+        Variable::Array(vec![str(&format!("{}/notes.txt", dir))])
+    );
+
+    let err = shrimp.call("glob", vec![str("[")]).unwrap_err();
+    assert!(format!("{:#}", err).contains("Invalid glob pattern `[`"));
+}
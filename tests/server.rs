@@ -0,0 +1,73 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use shrimp::{Interpreter, SharedBuffer, Variable};
+
+const SCRIPT: &str = "
+@echo(req) {
+    use map
+    map.new(\"X-Name\" req.params.name \"X-Page\" req.query.page) -> headers
+    con(req.method \" \" req.path \" \" req.body) -> body
+    map.new(\"status\" 201 \"headers\" headers \"body\" body) -> res
+    return res
+}
+@main(port) {
+    use server
+    server.new() -> app
+    server.route(app \"POST\" \"/items/:name\" echo)
+    server.listen(app port 3)
+}";
+
+/// Sends a raw request, and reads the whole response.
+fn send(addr: &str, req: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(req.as_bytes()).unwrap();
+
+    let mut res = String::new();
+    stream.read_to_string(&mut res).unwrap();
+    res
+}
+
+#[test]
+fn serves_requests_with_shrimp_handlers() {
+    let out = SharedBuffer::new();
+    let stdout = out.clone();
+
+    let server = thread::spawn(move || {
+        let mut shrimp = Interpreter::new();
+        shrimp.set_stdout(stdout);
+        shrimp.load(SCRIPT).unwrap();
+        shrimp.call("main", vec![Variable::Num(0.0)])
+    });
+
+    // Port 0 picks a free port, which the server prints.
+    let addr = loop {
+        if let Some(addr) = out.contents().trim().strip_prefix("Listening on http://") {
+            break addr.to_string();
+        }
+        assert!(!server.is_finished(), "the server stopped before listening");
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let res = send(
+        &addr,
+        "POST /items/big%20shrimp?page=2 HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello",
+    );
+    assert!(res.starts_with("HTTP/1.1 201 Created\r\n"));
+    assert!(res.contains("\r\nx-name: big shrimp\r\n"));
+    assert!(res.contains("\r\nx-page: 2\r\n"));
+    assert!(res.ends_with("\r\n\r\nPOST /items/big shrimp hello"));
+
+    let res = send(&addr, "GET /nothing HTTP/1.1\r\n\r\n");
+    assert!(res.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    // A query that isn't UTF-8 never reaches the handler.
+    let res = send(&addr, "POST /items/x?page=%FF HTTP/1.1\r\n\r\n");
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{}", res);
+    assert!(res.contains("Invalid UTF-8"), "{}", res);
+
+    // All the requests have been handled, so `listen` returns.
+    assert_eq!(server.join().unwrap().unwrap(), Variable::Void);
+}
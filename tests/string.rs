@@ -0,0 +1,149 @@
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use string
+@length(x) { len(x) -> out return out }
+@find(x pattern) {
+    decl out []
+    string.find(x pattern) -> first
+    string.rfind(x pattern) -> last
+    push(out first last) -> out
+    return out
+}
+@slice(x start end) { string.slice(x start end) -> out return out }
+@repeat(x times) { string.repeat(x times) -> out return out }
+@pad(x width fill) {
+    decl out []
+    string.pad_left(x width fill) -> left
+    string.pad_right(x width fill) -> right
+    push(out left right) -> out
+    return out
+}
+@replace_first(x from to count) { string.replace(x from to count) -> out return out }
+@global_replace(x from to) { replace(x from to) -> out return out }
+@split_lines(x) { string.lines(x) -> out return out }
+@join(arr separator) { string.join(arr separator) -> out return out }
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn num(n: f64) -> Variable {
+    Variable::Num(n)
+}
+
+#[test]
+fn counts_characters_not_bytes() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        shrimp.call("length", vec![str("héllo🦐")]).unwrap(),
+        num(6.0)
+    );
+    assert_eq!(
+        shrimp.call("find", vec![str("🦐é🦐é"), str("é")]).unwrap(),
+        Variable::Array(vec![num(1.0), num(3.0)])
+    );
+    assert_eq!(
+        shrimp.call("find", vec![str("abc"), str("z")]).unwrap(),
+        Variable::Array(vec![Variable::Void, Variable::Void])
+    );
+    assert_eq!(
+        shrimp
+            .call("slice", vec![str("🦐héllo"), num(1.0), num(3.0)])
+            .unwrap(),
+        str("hé")
+    );
+    assert_eq!(
+        shrimp
+            .call("pad", vec![str("é"), num(3.0), str("·")])
+            .unwrap(),
+        Variable::Array(vec![str("··é"), str("é··")])
+    );
+    assert_eq!(
+        shrimp.call("repeat", vec![str("é🦐"), num(3.0)]).unwrap(),
+        str("é🦐é🦐é🦐")
+    );
+}
+
+#[test]
+fn bad_indexes_and_counts_are_errors() {
+    let mut shrimp = shrimp();
+
+    let err = shrimp
+        .call("slice", vec![str("🦐🦐"), num(1.0), num(3.0)])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("len is 2 but index is 3"));
+
+    let err = shrimp
+        .call("repeat", vec![str("ab"), num(-1.0)])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Expected a whole number, found -1"));
+
+    let err = shrimp
+        .call("pad", vec![str("x"), num(3.0), str("ab")])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Expected a single character, found `ab`"));
+}
+
+#[test]
+fn huge_repeats_are_errors() {
+    let mut shrimp = shrimp();
+
+    // These used to panic, or abort when out of memory.
+    let err = shrimp
+        .call("repeat", vec![str("ab"), num(2f64.powi(62))])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Not enough memory to repeat"));
+
+    let err = shrimp
+        .call("pad", vec![str("x"), num(2f64.powi(62)), str("é")])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Not enough memory to repeat"));
+}
+
+#[test]
+fn replaces_splits_and_joins() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        shrimp
+            .call(
+                "replace_first",
+                vec![str("a-b-c"), str("-"), str("+"), num(1.0)]
+            )
+            .unwrap(),
+        str("a+b-c")
+    );
+    assert_eq!(
+        shrimp
+            .call("global_replace", vec![str("a-b-c"), str("-"), str("")])
+            .unwrap(),
+        str("abc")
+    );
+    assert_eq!(
+        shrimp
+            .call("split_lines", vec![str("one\r\ntwo\nthree")])
+            .unwrap(),
+        Variable::Array(vec![str("one"), str("two"), str("three")])
+    );
+    assert_eq!(
+        shrimp
+            .call(
+                "join",
+                vec![
+                    Variable::Array(vec![str("a"), num(1.0), Variable::Bool(true)]),
+                    str(", ")
+                ]
+            )
+            .unwrap(),
+        str("a, 1, true")
+    );
+}
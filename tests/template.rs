@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use template
+@render(source values) { template.render(source values) -> out return out }
+@render_text(source values) { template.render_text(source values) -> out return out }
+@render_file(path values) {
+    template.load(path) -> page
+    template.render(page values) -> out
+    return out
+}
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn values(pairs: &[(&str, Variable)]) -> Variable {
+    Variable::Map(
+        pairs
+            .iter()
+            .map(|(name, var)| (name.to_string(), var.clone()))
+            .collect(),
+    )
+}
+
+fn render(source: &str, pairs: &[(&str, Variable)]) -> anyhow::Result<Variable> {
+    shrimp().call("render", vec![str(source), values(pairs)])
+}
+
+fn html(s: &str) -> Variable {
+    Variable::Html(s.to_string())
+}
+
+#[test]
+fn loops_over_arrays_and_maps() {
+    let items = Variable::Array(vec![str("a"), str("b"), str("c")]);
+    assert_eq!(
+        render(
+            "{% for item in items %}{{ loop.index }}:{{ item }}{% if not loop.last %}, {% endif %}{% endfor %}",
+            &[("items", items)],
+        )
+        .unwrap(),
+        html("0:a, 1:b, 2:c")
+    );
+
+    let mut map = BTreeMap::new();
+    map.insert("x".to_string(), Variable::Num(1.0));
+    map.insert("y".to_string(), Variable::Num(2.0));
+    assert_eq!(
+        render(
+            "{% for key, value in map %}{{ key }}={{ value }};{% endfor %}{% for key in map %}{{ key }}{% endfor %}",
+            &[("map", Variable::Map(map))],
+        )
+        .unwrap(),
+        html("x=1;y=2;xy")
+    );
+}
+
+#[test]
+fn picks_the_first_true_branch() {
+    let source = "{% if admin %}admin{% elif user.name %}{{ user.name }}{% else %}guest{% endif %}";
+
+    let user = values(&[("name", str("Ann"))]);
+    assert_eq!(
+        render(
+            source,
+            &[("admin", Variable::Bool(true)), ("user", user.clone())]
+        )
+        .unwrap(),
+        html("admin")
+    );
+    assert_eq!(
+        render(source, &[("admin", Variable::Num(0.0)), ("user", user)]).unwrap(),
+        html("Ann")
+    );
+    // Missing and empty values are false.
+    assert_eq!(
+        render(source, &[("user", values(&[("name", str(""))]))]).unwrap(),
+        html("guest")
+    );
+}
+
+#[test]
+fn tags_on_their_own_lines_leave_no_blank_lines() {
+    let source = "<ul>\n{% for x in xs %}\n  <li>{{ x }}</li>\n{% endfor %}\n</ul>";
+
+    assert_eq!(
+        render(source, &[("xs", Variable::Array(vec![str("a"), str("b")]))]).unwrap(),
+        html("<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>")
+    );
+}
+
+#[test]
+fn escapes_values_by_default() {
+    let pairs = [
+        ("text", str("<b>\"Tom & Jerry\"</b>")),
+        ("trusted", html("<i>ok</i>")),
+    ];
+
+    assert_eq!(
+        render("{{ text }} {{ trusted }}", &pairs).unwrap(),
+        html("&lt;b&gt;&quot;Tom &amp; Jerry&quot;&lt;/b&gt; <i>ok</i>")
+    );
+    assert_eq!(
+        shrimp()
+            .call("render_text", vec![str("{{ text }}"), values(&pairs)])
+            .unwrap(),
+        str("<b>\"Tom & Jerry\"</b>")
+    );
+}
+
+#[test]
+fn includes_other_templates() {
+    let dir = std::env::temp_dir().join(format!("shrimp-template-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("parts")).unwrap();
+    fs::write(
+        dir.join("page.html"),
+        "{% include \"parts/header.html\" %}\n<p>{{ body }}</p>",
+    )
+    .unwrap();
+    fs::write(dir.join("parts/header.html"), "<h1>{{ title }}</h1>\n").unwrap();
+    fs::write(dir.join("loop.html"), "{% include \"loop.html\" %}").unwrap();
+
+    let page = dir.join("page.html").display().to_string();
+    let pairs = values(&[("title", str("Hi")), ("body", str("there"))]);
+    assert_eq!(
+        shrimp()
+            .call("render_file", vec![str(&page), pairs.clone()])
+            .unwrap(),
+        html("<h1>Hi</h1>\n<p>there</p>")
+    );
+
+    let looped = dir.join("loop.html").display().to_string();
+    let err = shrimp()
+        .call("render_file", vec![str(&looped), pairs])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("`loop.html` includes itself"));
+}
+
+#[test]
+fn errors_say_which_line() {
+    for (source, message) in [
+        (
+            "<ul>\n\n{% for x in xs %}\n<li>{{ x }}</li>\n",
+            "template, line 3: `for` is never closed",
+        ),
+        (
+            "one\n{% if a %}\ntwo\n",
+            "template, line 2: `if` is never closed",
+        ),
+        ("one\ntwo {{ x\n", "template, line 2: `{{` is never closed"),
+        (
+            "{# a\ncomment #}\n{% endfro %}",
+            "template, line 3: Unknown tag `endfro`",
+        ),
+        (
+            "{% if a %}\n{% endif %}\n\n{{ missing }}",
+            "template, line 4: `missing` is not defined",
+        ),
+        ("{{ a b }}", "template, line 1: `a b` is not a variable"),
+    ] {
+        let err = format!("{:#}", render(source, &[]).unwrap_err());
+        assert!(err.contains(message), "{:?}: {}", source, err);
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use shrimp::test_runner;
+
+/// A fresh directory for one test's Shrimp files.
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "shrimp-test-runner-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(dir: &Path, name: &str, source: &str) -> String {
+    let path = dir.join(name);
+    fs::write(&path, source).unwrap();
+    path.display().to_string()
+}
+
+/// Runs the files, returning whether they passed and the report.
+fn run(files: &[String]) -> (bool, String) {
+    let mut out = vec![];
+    let passed = test_runner::run_files(files, &mut out).unwrap();
+    (passed, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn only_finds_the_files_own_tests() {
+    let dir = dir("discover");
+    write(
+        &dir,
+        "helper.imp",
+        "@test_helper() { }\n@helper() { return 1 }",
+    );
+    let file = write(
+        &dir,
+        "main_test.imp",
+        "use helper\n@test_b() { }\n@test_a() { }\n@test_takes_args(x) { }\n@not_a_test() { }",
+    );
+
+    assert_eq!(
+        test_runner::discover(&file).unwrap(),
+        vec!["test_a", "test_b"]
+    );
+}
+
+#[test]
+fn reports_passes_and_failures() {
+    let dir = dir("report");
+    let file = write(
+        &dir,
+        "math_test.imp",
+        "@test_ok() { assert_eq(add(1 1) 2) }\n@test_bad() { println(\"working on it\") assert_eq(1 2) }",
+    );
+
+    let (passed, out) = run(&[file]);
+
+    assert!(!passed);
+    assert!(out.contains("test test_ok ... ok"));
+    assert!(out.contains("test test_bad ... FAILED"));
+    // What a failing test printed is shown along with why it failed.
+    assert!(out.contains("---- test_bad"));
+    assert!(out.contains("working on it"));
+    assert!(out.contains("1 passed; 1 failed"));
+}
+
+#[test]
+fn each_test_gets_a_fresh_interpreter() {
+    let dir = dir("fresh");
+    let file = write(
+        &dir,
+        "state_test.imp",
+        "@test_a() { decl leaked 1 }\n\
+         @test_b() {\n\
+             decl seen false\n\
+             try { println(leaked) decl seen true }\n\
+             catch err { println(err) }\n\
+             assert(eq(seen false))\n\
+         }",
+    );
+
+    let (passed, out) = run(&[file]);
+
+    assert!(passed, "{}", out);
+}
+
+#[test]
+fn a_file_that_fails_to_load_does_not_stop_the_rest() {
+    let dir = dir("load");
+    let bad = write(&dir, "bad_test.imp", "@test_x() {\n\t`\n}");
+    let good = write(&dir, "good_test.imp", "@test_ok() { }");
+
+    let (passed, out) = run(&[bad, good]);
+
+    assert!(!passed);
+    assert!(out.contains("failed to load"));
+    assert!(out.contains("Unexpected character"));
+    assert!(out.contains("test test_ok ... ok"));
+    assert!(out.contains("1 passed; 1 failed"));
+}
@@ -0,0 +1,143 @@
+use shrimp::{Interpreter, Variable};
+
+const SCRIPT: &str = "
+use time
+@format_utc(ms pattern) { time.format_utc(ms pattern) -> out return out }
+@parse_utc(text pattern) { time.parse_utc(text pattern) -> out return out }
+@round_trip(ms pattern) {
+    time.format(ms pattern) -> text
+    time.parse(text pattern) -> out
+    return out
+}
+@durations() {
+    decl out []
+    time.seconds(1.5) -> s
+    time.minutes(2) -> m
+    time.hours(1) -> h
+    time.days(1) -> d
+    push(out s m h d) -> out
+    return out
+}
+@sleep(ms) {
+    time.monotonic() -> start
+    time.sleep(ms)
+    time.elapsed(start) -> out
+    return out
+}
+";
+
+fn shrimp() -> Interpreter {
+    let mut shrimp = Interpreter::new();
+    shrimp.load(SCRIPT).unwrap();
+    shrimp
+}
+
+fn str(s: &str) -> Variable {
+    Variable::Str(s.to_string())
+}
+
+fn num(n: f64) -> Variable {
+    Variable::Num(n)
+}
+
+/// 2021-03-04 05:06:07.089 UTC
+const MS: f64 = 1_614_834_367_089.0;
+
+#[test]
+fn formats_and_parses_timestamps() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        shrimp
+            .call("format_utc", vec![num(MS), str("%Y-%m-%d %H:%M:%S%.3f")])
+            .unwrap(),
+        str("2021-03-04 05:06:07.089")
+    );
+    assert_eq!(
+        shrimp
+            .call(
+                "parse_utc",
+                vec![str("2021-03-04 05:06:07"), str("%Y-%m-%d %H:%M:%S")]
+            )
+            .unwrap(),
+        num(MS - 89.0)
+    );
+    // No time means midnight.
+    assert_eq!(
+        shrimp
+            .call("parse_utc", vec![str("2021-03-04"), str("%Y-%m-%d")])
+            .unwrap(),
+        num(1_614_816_000_000.0)
+    );
+    // An explicit zone wins over the one asked for.
+    assert_eq!(
+        shrimp
+            .call(
+                "parse_utc",
+                vec![
+                    str("2021-03-04 07:06:07 +0200"),
+                    str("%Y-%m-%d %H:%M:%S %z")
+                ]
+            )
+            .unwrap(),
+        num(MS - 89.0)
+    );
+    // Local time, whatever the zone is, comes back the same way.
+    assert_eq!(
+        shrimp
+            .call("round_trip", vec![num(MS - 89.0), str("%Y-%m-%d %H:%M:%S")])
+            .unwrap(),
+        num(MS - 89.0)
+    );
+}
+
+#[test]
+fn bad_times_are_errors() {
+    let mut shrimp = shrimp();
+
+    let err = shrimp
+        .call("format_utc", vec![num(MS), str("%Q")])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Invalid time format `%Q`"));
+
+    let err = shrimp
+        .call("parse_utc", vec![str("yesterday"), str("%Y-%m-%d")])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Failed to parse `yesterday`"));
+
+    let err = shrimp
+        .call("format_utc", vec![num(1e300), str("%Y")])
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("is out of range"));
+}
+
+#[test]
+fn durations_are_milliseconds() {
+    let mut shrimp = shrimp();
+
+    assert_eq!(
+        shrimp.call("durations", vec![]).unwrap(),
+        Variable::Array(vec![
+            num(1_500.0),
+            num(120_000.0),
+            num(3_600_000.0),
+            num(86_400_000.0),
+        ])
+    );
+}
+
+#[test]
+fn sleeps() {
+    let mut shrimp = shrimp();
+
+    match shrimp.call("sleep", vec![num(20.0)]).unwrap() {
+        Variable::Num(elapsed) => assert!(elapsed >= 20.0, "{}", elapsed),
+        any => panic!("expected a number, found {:?}", any),
+    }
+
+    // Negative durations don't sleep at all, but ones that can't be
+    // represented are errors rather than panics.
+    shrimp.call("sleep", vec![num(-5.0)]).unwrap();
+    let err = shrimp.call("sleep", vec![num(f64::INFINITY)]).unwrap_err();
+    assert!(format!("{:#}", err).contains("inf milliseconds is not a valid duration"));
+}